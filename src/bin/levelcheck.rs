@@ -0,0 +1,111 @@
+/*******************************************************************************
+ *
+ * Copyright (c) 2020 Gnarwhal
+ *
+ * -----------------------------------------------------------------------------
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files(the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ *
+ *******************************************************************************/
+
+use std::{
+	env,
+	path::{Path, PathBuf},
+	process,
+};
+
+use amethyst::{
+	config::Config,
+	utils::application_root_dir,
+};
+use djam_4::levels::{LevelManifest, DEFAULT_PALETTE};
+use djam_4::levels::check;
+use djam_4::levels::LevelIssue;
+
+// A palette given on the command line is used for every level, including the
+// ones in the manifest.
+const USAGE: &str = "usage: djam4-levelcheck [--palette <palette.ron>] [--manifest <manifest.ron>] [level...]";
+
+fn report(path: &Path, issues: Vec<LevelIssue>) -> bool {
+	if issues.is_empty() {
+		println!("{}: ok", path.display());
+		true
+	} else {
+		println!("{}:", path.display());
+		for issue in issues {
+			println!("\t{}", issue);
+		}
+		false
+	}
+}
+
+fn main() {
+	let resources_dir = match application_root_dir() {
+		Ok(app_root) => app_root.join("resources"),
+		Err(error) => {
+			eprintln!("error: could not find the application directory: {}", error);
+			process::exit(1);
+		},
+	};
+	let mut arguments = env::args().skip(1);
+	let mut palette_path = None;
+	let mut manifest_path = resources_dir.join("levels").join("manifest.ron");
+	let mut paths = Vec::<PathBuf>::new();
+	while let Some(argument) = arguments.next() {
+		match argument.as_str() {
+			"--palette" | "--manifest" => match arguments.next() {
+				Some(path) if argument == "--palette" => palette_path = Some(PathBuf::from(path)),
+				Some(path) => manifest_path = PathBuf::from(path),
+				None => {
+					eprintln!("{}", USAGE);
+					process::exit(2);
+				},
+			},
+			"--help" | "-h" => {
+				println!("{}", USAGE);
+				return;
+			},
+			_ => paths.push(PathBuf::from(argument)),
+		}
+	}
+
+	let mut passed = true;
+	if paths.is_empty() {
+		let manifest = match LevelManifest::load(&manifest_path) {
+			Ok(manifest) => manifest,
+			Err(error) => {
+				eprintln!("error: could not load manifest '{}': {}", manifest_path.display(), error);
+				process::exit(1);
+			},
+		};
+		for entry in &manifest.levels {
+			let entry_palette_path = palette_path.clone().unwrap_or_else(|| resources_dir.join(entry.palette()));
+			passed &= report(&resources_dir.join(&entry.file), check::check_entry(entry, &resources_dir, &entry_palette_path));
+		}
+	} else {
+		let palette_path = palette_path.unwrap_or_else(|| resources_dir.join(DEFAULT_PALETTE));
+		for path in &paths {
+			passed &= report(path, check::check_level(path, &palette_path, None));
+		}
+	}
+
+	if !passed {
+		process::exit(1);
+	}
+}
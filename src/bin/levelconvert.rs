@@ -0,0 +1,79 @@
+/*******************************************************************************
+ *
+ * Copyright (c) 2020 Gnarwhal
+ *
+ * -----------------------------------------------------------------------------
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files(the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ *
+ *******************************************************************************/
+
+use std::{
+	env,
+	path::PathBuf,
+	process,
+};
+
+use amethyst::utils::application_root_dir;
+use djam_4::levels;
+use djam_4::levels::DEFAULT_PALETTE;
+
+fn main() {
+	let resources_dir = match application_root_dir() {
+		Ok(app_root) => app_root.join("resources"),
+		Err(error) => {
+			eprintln!("error: could not find the application directory: {}", error);
+			process::exit(1);
+		},
+	};
+	let mut arguments = env::args().skip(1);
+	let mut palette_path = resources_dir.join(DEFAULT_PALETTE);
+	let mut paths = Vec::<PathBuf>::new();
+	while let Some(argument) = arguments.next() {
+		match argument.as_str() {
+			"--palette" => match arguments.next() {
+				Some(path) => palette_path = PathBuf::from(path),
+				None => {
+					eprintln!("--palette expects a path");
+					process::exit(2);
+				},
+			},
+			_ => paths.push(PathBuf::from(argument)),
+		}
+	}
+	if paths.len() != 2 {
+		eprintln!("usage: djam4-levelconvert [--palette <palette.ron>] <input.png|input.ron|input.tmx|input.tmj> <output.png|output.ron>");
+		process::exit(2);
+	}
+
+	let level = match levels::read_level(&paths[0], &palette_path) {
+		Ok(level) => level,
+		Err(error) => {
+			eprintln!("error: {}", error);
+			process::exit(1);
+		},
+	};
+	if !level.layers.is_empty() {
+		eprintln!("warning: bitmap and text levels can't hold decoration layers, dropping {} layer(s)", level.layers.len());
+	}
+	if let Err(error) = levels::write_level(&level, &paths[1], &palette_path) {
+		eprintln!("error: {}", error);
+		process::exit(1);
+	}
+}
@@ -0,0 +1,63 @@
+/*******************************************************************************
+ *
+ * Copyright (c) 2020 Gnarwhal
+ *
+ * -----------------------------------------------------------------------------
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files(the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ *
+ *******************************************************************************/
+
+use amethyst::{
+	core::math::Vector3,
+	ecs::prelude::{Component, DenseVecStorage},
+};
+
+/// Smooths an entity moved by the fixed step physics, drawing it between
+/// where it was before and after the last step.
+#[derive(Clone, Copy)]
+pub struct Interpolation {
+	pub previous: Vector3<f32>,
+	pub current: Vector3<f32>,
+	/// Where the entity was last drawn. If its transform no longer matches,
+	/// something outside the physics moved it.
+	pub rendered: Vector3<f32>,
+}
+
+impl Interpolation {
+	pub fn new(position: Vector3<f32>) -> Self {
+		Interpolation {
+			previous: position,
+			current: position,
+			rendered: position,
+		}
+	}
+
+	/// Moves straight to a position instead of blending over from the old
+	/// one.
+	pub fn teleport(&mut self, position: Vector3<f32>) {
+		self.previous = position;
+		self.current = position;
+		self.rendered = position;
+	}
+}
+
+impl Component for Interpolation {
+	type Storage = DenseVecStorage<Self>;
+}
@@ -1,63 +1,107 @@
-/*******************************************************************************
- *
- * Copyright (c) 2020 Gnarwhal
- *
- * -----------------------------------------------------------------------------
- *
- * Permission is hereby granted, free of charge, to any person obtaining a copy
- * of this software and associated documentation files(the "Software"), to deal
- * in the Software without restriction, including without limitation the rights
- * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
- * copies of the Software, and to permit persons to whom the Software is
- * furnished to do so, subject to the following conditions
- *
- * The above copyright notice and this permission notice shall be included in all
- * copies or substantial portions of the Software.
- *
- * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
- * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
- * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
- * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
- * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
- * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
- * SOFTWARE.
- *
- *******************************************************************************/
-
-use core::default::Default;
-use amethyst::{
-	core::math::Vector2,
-	ecs::prelude::{Component, DenseVecStorage, NullStorage}
-};
-
-pub struct Gravity;
-
-impl Component for Gravity {
-	type Storage = NullStorage<Self>;
-}
-
-impl Default for Gravity {
-	fn default() -> Self {
-		Gravity
-	}
-}
-
-pub struct Dynamic {
-	pub velocity: Vector2<f32>,
-	pub grounded: bool,
-	pub friction_coefficient: f32,
-}
-
-impl Default for Dynamic {
-	fn default() -> Self {
-		Dynamic {
-			velocity: Vector2::new(0.0, 0.0),
-			grounded: false,
-			friction_coefficient: 1.0,
-		}
-	}
-}
-
-impl Component for Dynamic {
-	type Storage = DenseVecStorage<Self>;
-}
+/*******************************************************************************
+ *
+ * Copyright (c) 2020 Gnarwhal
+ *
+ * -----------------------------------------------------------------------------
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files(the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ *
+ *******************************************************************************/
+
+use core::default::Default;
+use amethyst::{
+	core::math::{Vector2, Vector3},
+	ecs::prelude::{Component, DenseVecStorage, NullStorage}
+};
+use crate::components::Surface;
+use crate::states::level::BLOCK_SIZE;
+
+pub struct Gravity;
+
+impl Component for Gravity {
+	type Storage = NullStorage<Self>;
+}
+
+impl Default for Gravity {
+	fn default() -> Self {
+		Gravity
+	}
+}
+
+#[derive(Clone)]
+pub struct Dynamic {
+	pub velocity: Vector2<f32>,
+	pub grounded: bool,
+	pub friction_coefficient: f32,
+	/// The surface of the tile the body last landed on.
+	pub surface: Surface,
+	/// Falls through one-way platforms while set.
+	pub drop_through: bool,
+}
+
+impl Default for Dynamic {
+	fn default() -> Self {
+		Dynamic {
+			velocity: Vector2::new(0.0, 0.0),
+			grounded: false,
+			friction_coefficient: 1.0,
+			surface: Surface::default(),
+			drop_through: false,
+		}
+	}
+}
+
+impl Component for Dynamic {
+	type Storage = DenseVecStorage<Self>;
+}
+
+/// The box a body collides with the level as, centered `offset` away from its
+/// translation. Bodies without one are a block in size.
+#[derive(Clone, Copy, Debug)]
+pub struct Collider {
+	pub half_extents: Vector2<f32>,
+	pub offset: Vector2<f32>,
+}
+
+impl Collider {
+	pub fn new(half_extents: Vector2<f32>, offset: Vector2<f32>) -> Self {
+		Collider {
+			half_extents,
+			offset,
+		}
+	}
+
+	pub fn center(&self, translation: &Vector3<f32>) -> Vector2<f32> {
+		Vector2::new(translation.x + self.offset.x, translation.y + self.offset.y)
+	}
+
+	pub fn bottom(&self, translation: &Vector3<f32>) -> f32 {
+		translation.y + self.offset.y - self.half_extents.y
+	}
+}
+
+impl Default for Collider {
+	fn default() -> Self {
+		Collider::new(Vector2::new(BLOCK_SIZE / 2.0, BLOCK_SIZE / 2.0), Vector2::new(0.0, 0.0))
+	}
+}
+
+impl Component for Collider {
+	type Storage = DenseVecStorage<Self>;
+}
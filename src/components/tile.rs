@@ -1,47 +1,137 @@
-/*******************************************************************************
- *
- * Copyright (c) 2020 Gnarwhal
- *
- * -----------------------------------------------------------------------------
- *
- * Permission is hereby granted, free of charge, to any person obtaining a copy
- * of this software and associated documentation files(the "Software"), to deal
- * in the Software without restriction, including without limitation the rights
- * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
- * copies of the Software, and to permit persons to whom the Software is
- * furnished to do so, subject to the following conditions
- *
- * The above copyright notice and this permission notice shall be included in all
- * copies or substantial portions of the Software.
- *
- * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
- * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
- * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
- * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
- * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
- * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
- * SOFTWARE.
- *
- *******************************************************************************/
-
-use core::default::Default;
-use amethyst::{
-	ecs::prelude::{Component, DenseVecStorage},
-};
-
-pub enum Tile {
-	Background,
-	Ground,
-	Start,
-	End,
-}
-
-impl Default for Tile {
-	fn default() -> Self {
-		Tile::Background
-	}
-}
-
-impl Component for Tile {
-	type Storage = DenseVecStorage<Self>;
-}
+/*******************************************************************************
+ *
+ * Copyright (c) 2020 Gnarwhal
+ *
+ * -----------------------------------------------------------------------------
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files(the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ *
+ *******************************************************************************/
+
+use core::default::Default;
+use amethyst::{
+	core::math::Point3,
+	ecs::World,
+	tiles::Tile as RenderTile,
+};
+use serde::{Serialize, Deserialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Tile {
+	Background,
+	Ground,
+	Start,
+	End,
+	Ice,
+	Mud,
+	Bounce,
+	/// Only stops bodies falling onto it from above.
+	Platform,
+	Spikes,
+	Lava,
+	/// Becomes the player's respawn point when touched.
+	Checkpoint,
+	/// 45 degree slopes, named for the direction they rise towards.
+	SlopeRight,
+	SlopeLeft,
+	/// 22.5 degree slopes, which take two tiles to rise a block. The low
+	/// half goes on the side the slope starts from.
+	GentleSlopeRightLow,
+	GentleSlopeRightHigh,
+	GentleSlopeLeftLow,
+	GentleSlopeLeftHigh,
+}
+
+/// How a tile affects a body standing on it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Surface {
+	/// Scales both ground friction and how quickly bodies can speed up.
+	pub friction: f32,
+	/// How much of the landing speed bounces back up.
+	pub bounce: f32,
+	/// Scales the top running speed.
+	pub speed: f32,
+}
+
+impl Default for Surface {
+	fn default() -> Self {
+		Surface {
+			friction: 1.0,
+			bounce: 0.0,
+			speed: 1.0,
+		}
+	}
+}
+
+impl Tile {
+	pub fn is_solid(self) -> bool {
+		match self {
+			Tile::Background | Tile::Platform | Tile::Spikes | Tile::Lava | Tile::Checkpoint => false,
+			Tile::Ground | Tile::Start | Tile::End | Tile::Ice | Tile::Mud | Tile::Bounce => true,
+			// Slopes are only stood on, see `slope`
+			Tile::SlopeRight | Tile::SlopeLeft
+			| Tile::GentleSlopeRightLow | Tile::GentleSlopeRightHigh
+			| Tile::GentleSlopeLeftLow | Tile::GentleSlopeLeftHigh => false,
+		}
+	}
+
+	/// The height of a slope's floor at its left and right edges, as a
+	/// fraction of a block. None if the tile isn't a slope.
+	pub fn slope(self) -> Option<(f32, f32)> {
+		match self {
+			Tile::SlopeRight           => Some((0.0, 1.0)),
+			Tile::SlopeLeft            => Some((1.0, 0.0)),
+			Tile::GentleSlopeRightLow  => Some((0.0, 0.5)),
+			Tile::GentleSlopeRightHigh => Some((0.5, 1.0)),
+			Tile::GentleSlopeLeftLow   => Some((0.5, 0.0)),
+			Tile::GentleSlopeLeftHigh  => Some((1.0, 0.5)),
+			_ => None,
+		}
+	}
+
+	/// Whether touching the tile kills the player.
+	pub fn is_hazard(self) -> bool {
+		matches!(self, Tile::Spikes | Tile::Lava)
+	}
+
+	pub fn surface(self) -> Surface {
+		match self {
+			Tile::Ice    => Surface { friction: 0.1, ..Surface::default() },
+			Tile::Mud    => Surface { friction: 2.0, speed: 0.4, ..Surface::default() },
+			Tile::Bounce => Surface { bounce: 1.0, ..Surface::default() },
+			_ => Surface::default(),
+		}
+	}
+}
+
+impl Default for Tile {
+	fn default() -> Self {
+		Tile::Background
+	}
+}
+
+/// The sprite drawn for a single cell of a level's tile map.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TileSprite(pub Option<usize>);
+
+impl RenderTile for TileSprite {
+	fn sprite(&self, _: Point3<u32>, _: &World) -> Option<usize> {
+		self.0
+	}
+}
@@ -0,0 +1,145 @@
+/*******************************************************************************
+ *
+ * Copyright (c) 2020 Gnarwhal
+ *
+ * -----------------------------------------------------------------------------
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files(the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ *
+ *******************************************************************************/
+
+use std::{
+	fs,
+	io,
+	path::{Path, PathBuf},
+	sync::atomic::{AtomicUsize, Ordering},
+};
+
+use amethyst::{
+	assets::{Asset, Format, Handle},
+	ecs::VecStorage,
+	Error,
+};
+use image::RgbaImage;
+use crate::levels::{self, bitmap, text, tiled, LayerEntry, LevelData, LevelLayer, LevelLoadError, Palette};
+
+// Every import gets a new version, which is how the level state notices a
+// level was hot reloaded.
+static NEXT_VERSION: AtomicUsize = AtomicUsize::new(1);
+
+#[derive(Clone, Debug)]
+pub enum LevelSource {
+	/// Bitmap levels can't be turned into tiles until their palette has
+	/// loaded as well, so they are kept as the decoded image.
+	Bitmap(RgbaImage),
+	Data(LevelData),
+}
+
+#[derive(Clone, Debug)]
+pub struct LevelAsset {
+	pub path: PathBuf,
+	pub source: LevelSource,
+	pub version: usize,
+}
+
+impl LevelAsset {
+	/// Imports a level from its bytes. The path is the one the level is
+	/// loaded from, which decides the format.
+	pub fn import(bytes: &[u8], path: &Path) -> Result<Self, LevelLoadError> {
+		let source = match levels::extension(path).as_deref() {
+			Some("png") => LevelSource::Bitmap(bitmap::decode(bytes, path)?),
+			Some("ron") => LevelSource::Data(text::from_bytes(bytes, path)?),
+			Some("tmx") => LevelSource::Data(tiled::tmx_from_bytes(bytes, path)?),
+			Some("tmj") | Some("json") => LevelSource::Data(tiled::tmj_from_bytes(bytes, path)?),
+			_ => return Err(LevelLoadError::UnknownFormat(path.to_owned())),
+		};
+		Ok(LevelAsset {
+			path: path.to_owned(),
+			source,
+			version: NEXT_VERSION.fetch_add(1, Ordering::Relaxed),
+		})
+	}
+
+	/// Reads and imports a level without the asset loader, for tools that run
+	/// outside of the game. `file` is where the level is on disk and `path`
+	/// is the one the game loads it from.
+	pub fn open(file: &Path, path: &Path) -> Result<Self, LevelLoadError> {
+		let bytes = fs::read(file).map_err(|error| match error.kind() {
+			io::ErrorKind::NotFound => LevelLoadError::Missing(file.to_owned()),
+			_ => LevelLoadError::Io(file.to_owned(), error),
+		})?;
+		LevelAsset::import(&bytes, path)
+	}
+
+	/// The level's tiles, without requiring a start like `to_level` does.
+	pub fn to_data(&self, palette: &Palette) -> Result<LevelData, LevelLoadError> {
+		match &self.source {
+			LevelSource::Bitmap(level_image) => bitmap::from_image(level_image, palette, &self.path),
+			LevelSource::Data(level) => Ok(level.clone()),
+		}
+	}
+
+	pub fn to_level(&self, palette: &Palette) -> Result<LevelData, LevelLoadError> {
+		levels::require_start(self.to_data(palette)?, &self.path)
+	}
+
+	pub fn to_layer(&self, palette: &Palette, entry: &LayerEntry, width: usize, height: usize) -> Result<LevelLayer, LevelLoadError> {
+		let level = self.to_data(palette)?;
+		if level.width != width || level.height != height {
+			return Err(LevelLoadError::LayerSize(self.path.clone(), (level.width, level.height), (width, height)));
+		}
+		Ok(LevelLayer {
+			name: self.path.display().to_string(),
+			depth: entry.depth(),
+			scroll: entry.scroll(),
+			sprites: level.sprites,
+		})
+	}
+}
+
+impl Asset for LevelAsset {
+	const NAME: &'static str = "djam_4::LevelAsset";
+	type Data = Self;
+	type HandleStorage = VecStorage<Handle<Self>>;
+}
+
+/// Loads a level of any supported format. The path is the one the level is
+/// loaded from, relative to the resources directory, and decides the format.
+#[derive(Clone, Debug)]
+pub struct LevelFormat {
+	path: PathBuf,
+}
+
+impl LevelFormat {
+	pub fn new(path: &Path) -> Self {
+		LevelFormat {
+			path: path.to_owned(),
+		}
+	}
+}
+
+impl Format<LevelAsset> for LevelFormat {
+	fn name(&self) -> &'static str {
+		"Level"
+	}
+
+	fn import_simple(&self, bytes: Vec<u8>) -> Result<LevelAsset, Error> {
+		Ok(LevelAsset::import(&bytes, &self.path)?)
+	}
+}
@@ -0,0 +1,250 @@
+/*******************************************************************************
+ *
+ * Copyright (c) 2020 Gnarwhal
+ *
+ * -----------------------------------------------------------------------------
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files(the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ *
+ *******************************************************************************/
+
+use std::collections::BTreeMap;
+
+use amethyst::{
+	assets::{Asset, Handle},
+	ecs::VecStorage,
+};
+use serde::{Serialize, Deserialize};
+use crate::components::Tile;
+use crate::levels::LevelData;
+
+// Offsets to each neighbor in bitmask order, clockwise from straight up.
+// Rows count down from the top of the level.
+const FOUR_NEIGHBORS:  [(isize, isize); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+const EIGHT_NEIGHBORS: [(isize, isize); 8] = [(0, -1), (1, -1), (1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1)];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Connectivity {
+	/// Bits for up, right, down and left, in that order.
+	Four,
+	/// Bits for up, up right, right, down right, down, down left, left and
+	/// up left, in that order.
+	Eight,
+}
+
+impl Default for Connectivity {
+	fn default() -> Self {
+		Connectivity::Four
+	}
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AutoTileRule {
+	pub tile: Tile,
+	/// Only tiles drawn with this sprite are auto-tiled, so tiles the level
+	/// picked a different sprite for are left alone.
+	#[serde(default)]
+	pub sprite: Option<usize>,
+	#[serde(default)]
+	pub connectivity: Connectivity,
+	/// The sprite for each neighbor bitmask, where a set bit means that
+	/// neighbor is the same kind of tile. Masks without a sprite keep the
+	/// tile's own sprite.
+	pub sprites: BTreeMap<u8, usize>,
+}
+
+impl AutoTileRule {
+	// Neighbors past the edge of the level count as the same kind of tile,
+	// so the ground doesn't get an edge where the level is cut off.
+	pub fn mask(&self, level: &LevelData, x: usize, y: usize) -> u8 {
+		let neighbors: &[(isize, isize)] = match self.connectivity {
+			Connectivity::Four  => &FOUR_NEIGHBORS,
+			Connectivity::Eight => &EIGHT_NEIGHBORS,
+		};
+		let mut mask = 0;
+		for (bit, (dx, dy)) in neighbors.iter().enumerate() {
+			let nx = x as isize + dx;
+			let ny = y as isize + dy;
+			let connected = nx < 0 || ny < 0 || nx >= level.width as isize || ny >= level.height as isize
+				|| level.tile(nx as usize, ny as usize) == self.tile;
+			if connected {
+				mask |= 1 << bit;
+			}
+		}
+		mask
+	}
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct AutoTileRules {
+	pub rules: Vec<AutoTileRule>,
+}
+
+impl Asset for AutoTileRules {
+	const NAME: &'static str = "djam_4::AutoTileRules";
+	type Data = Self;
+	type HandleStorage = VecStorage<Handle<Self>>;
+}
+
+impl AutoTileRules {
+	/// Picks the sprite of every tile in the level, in the same order as
+	/// `LevelData::sprites`. The first rule matching a tile wins.
+	pub fn apply(&self, level: &LevelData) -> Vec<Option<usize>> {
+		let mut sprites = level.sprites.clone();
+		for y in 0..level.height {
+			for x in 0..level.width {
+				let i = y * level.width + x;
+				let rule = self.rules
+					.iter()
+					.find(|rule| rule.tile == level.tiles[i] && rule.sprite == level.sprites[i]);
+				if let Some(rule) = rule {
+					if let Some(&sprite) = rule.sprites.get(&rule.mask(level, x, y)) {
+						sprites[i] = Some(sprite);
+					}
+				}
+			}
+		}
+		sprites
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::BTreeMap;
+
+	use crate::components::Tile;
+	use crate::levels::LevelData;
+	use super::{AutoTileRule, AutoTileRules, Connectivity};
+
+	const GROUND_SPRITE: usize = 2;
+	const FIRST_MASK_SPRITE: usize = 5;
+
+	// '#' is ground drawn with the ground sprite, '.' is background.
+	fn level(rows: &[&str]) -> LevelData {
+		let width = rows[0].len();
+		let height = rows.len();
+		let (tiles, sprites) = rows
+			.iter()
+			.flat_map(|row| row.chars())
+			.map(|character| match character {
+				'#' => (Tile::Ground, Some(GROUND_SPRITE)),
+				_ => (Tile::Background, None),
+			})
+			.unzip();
+		LevelData::new(width, height, tiles, sprites)
+	}
+
+	fn rule(connectivity: Connectivity, masks: impl Iterator<Item = u8>) -> AutoTileRule {
+		AutoTileRule {
+			tile: Tile::Ground,
+			sprite: Some(GROUND_SPRITE),
+			connectivity,
+			sprites: masks.map(|mask| (mask, FIRST_MASK_SPRITE + mask as usize)).collect::<BTreeMap<_, _>>(),
+		}
+	}
+
+	fn masked(mask: u8) -> Option<usize> {
+		Some(FIRST_MASK_SPRITE + mask as usize)
+	}
+
+	#[test]
+	fn four_connected_masks() {
+		let level = level(&[
+			".##.",
+			"####",
+			".##.",
+			"#...",
+		]);
+		let rule = rule(Connectivity::Four, 0..16);
+		// Interior
+		assert_eq!(rule.mask(&level, 1, 1), 15);
+		assert_eq!(rule.mask(&level, 2, 1), 15);
+		// Edges, where the neighbor past the edge counts as connected
+		assert_eq!(rule.mask(&level, 1, 0), 7);
+		assert_eq!(rule.mask(&level, 0, 1), 10);
+		assert_eq!(rule.mask(&level, 3, 1), 10);
+		// Corners of the ground
+		assert_eq!(rule.mask(&level, 1, 2), 3);
+		assert_eq!(rule.mask(&level, 2, 2), 9);
+		// Corner of the level
+		assert_eq!(rule.mask(&level, 0, 3), 12);
+	}
+
+	#[test]
+	fn eight_connected_masks() {
+		let level = level(&[
+			"###",
+			"#.#",
+			"###",
+		]);
+		let rule = rule(Connectivity::Eight, 0..=255);
+		// Everything but the background down and to the right
+		assert_eq!(rule.mask(&level, 0, 0), 0b1111_0111);
+		// Everything but the background straight down
+		assert_eq!(rule.mask(&level, 1, 0), 0b1110_1111);
+		// Everything but the background up and to the left
+		assert_eq!(rule.mask(&level, 2, 2), 0b0111_1111);
+	}
+
+	#[test]
+	fn apply_picks_sprites_by_mask() {
+		let level = level(&[
+			"##.",
+			"##.",
+			"...",
+		]);
+		let rules = AutoTileRules { rules: vec![rule(Connectivity::Four, 0..16)] };
+		assert_eq!(rules.apply(&level), vec![
+			masked(15), masked(13), None,
+			masked(11), masked(9),  None,
+			None,       None,       None,
+		]);
+	}
+
+	#[test]
+	fn apply_falls_back_to_the_tile_sprite() {
+		let mut level = level(&[
+			"###",
+			"###",
+			"###",
+		]);
+		// A tile the level drew with a different sprite isn't auto-tiled
+		level.sprites[0] = Some(9);
+		// Only fully surrounded tiles have a sprite
+		let rules = AutoTileRules { rules: vec![rule(Connectivity::Four, 15..16)] };
+		let sprites = rules.apply(&level);
+		assert_eq!(sprites[0], Some(9));
+		assert!(sprites[1..].iter().all(|&sprite| sprite == masked(15)));
+
+		let rules = AutoTileRules { rules: vec![rule(Connectivity::Four, 0..15)] };
+		let sprites = rules.apply(&level);
+		assert_eq!(sprites[0], Some(9));
+		assert!(sprites[1..].iter().all(|&sprite| sprite == Some(GROUND_SPRITE)));
+	}
+
+	#[test]
+	fn apply_leaves_tiles_without_a_rule() {
+		let level = level(&[
+			"#.",
+			".#",
+		]);
+		let rules = AutoTileRules::default();
+		assert_eq!(rules.apply(&level), level.sprites);
+	}
+}
@@ -0,0 +1,91 @@
+/*******************************************************************************
+ *
+ * Copyright (c) 2020 Gnarwhal
+ *
+ * -----------------------------------------------------------------------------
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files(the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ *
+ *******************************************************************************/
+
+use std::path::Path;
+
+use image::{Rgba, RgbaImage};
+
+use crate::components::Tile;
+use crate::levels::{InvalidPixel, LevelData, LevelLoadError, LevelSaveError, Palette};
+
+pub fn decode(bytes: &[u8], path: &Path) -> Result<RgbaImage, LevelLoadError> {
+	image::load_from_memory(bytes)
+		.map(|level_image| level_image.into_rgba())
+		.map_err(|error| LevelLoadError::from_image(path.to_owned(), error))
+}
+
+pub fn from_image(level_image: &RgbaImage, palette: &Palette, path: &Path) -> Result<LevelData, LevelLoadError> {
+	let width = level_image.width() as usize;
+	let height = level_image.height() as usize;
+	let mut tiles = Vec::<Tile>::with_capacity(width * height);
+	let mut sprites = Vec::<Option<usize>>::with_capacity(width * height);
+	let mut invalid_pixels = Vec::<InvalidPixel>::new();
+	for (x, y, pixel) in level_image.enumerate_pixels() {
+		match palette.lookup(pixel.0) {
+			Some(entry) => {
+				tiles.push(entry.tile);
+				sprites.push(entry.sprite);
+			},
+			None => invalid_pixels.push(InvalidPixel { x, y, color: pixel.0 }),
+		}
+	}
+	if !invalid_pixels.is_empty() {
+		return Err(LevelLoadError::InvalidPixels(path.to_owned(), invalid_pixels));
+	}
+	Ok(LevelData::new(width, height, tiles, sprites))
+}
+
+pub fn load(path: &Path, palette: &Palette) -> Result<LevelData, LevelLoadError> {
+	let level_image = image::open(path)
+		.map_err(|error| LevelLoadError::from_image(path.to_owned(), error))?
+		.into_rgba();
+	from_image(&level_image, palette, path)
+}
+
+// Bitmaps only have room for tiles, so anything else in the level would be
+// lost.
+pub fn save(level: &LevelData, palette: &Palette, path: &Path) -> Result<(), LevelSaveError> {
+	let unsaved = [
+		(!level.starts.is_empty(), "start points"),
+		(!level.ends.is_empty(), "end areas"),
+		(!level.objects.is_empty(), "objects"),
+		(!level.properties.is_empty(), "tile properties"),
+	];
+	if let Some((_, what)) = unsaved.iter().find(|(present, _)| *present) {
+		return Err(LevelSaveError::Unsupported(path.to_owned(), *what));
+	}
+	let mut level_image = RgbaImage::new(level.width as u32, level.height as u32);
+	for (i, (&tile, &sprite)) in level.tiles.iter().zip(&level.sprites).enumerate() {
+		let color = match palette.reverse_lookup(tile, sprite) {
+			Some(entry) => entry.color,
+			None => return Err(LevelSaveError::NoColor(tile, sprite)),
+		};
+		level_image.put_pixel((i % level.width) as u32, (i / level.width) as u32, Rgba(color));
+	}
+	level_image
+		.save(path)
+		.map_err(|error| LevelSaveError::Image(path.to_owned(), error))
+}
@@ -0,0 +1,187 @@
+/*******************************************************************************
+ *
+ * Copyright (c) 2020 Gnarwhal
+ *
+ * -----------------------------------------------------------------------------
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files(the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ *
+ *******************************************************************************/
+
+use std::{
+	collections::VecDeque,
+	fmt::{self, Display},
+	path::Path,
+};
+
+use crate::levels::{LevelAsset, LevelData, LevelEntry, LevelLoadError, Palette, Reachability};
+use crate::levels::reachability;
+use crate::states::level::{BLOCK_SIZE, CAMERA_HEIGHT, CAMERA_WIDTH};
+
+#[derive(Debug)]
+pub enum LevelIssue {
+	Load(LevelLoadError),
+	NoStart,
+	MultipleStarts(usize),
+	MissingStart(usize, usize),
+	NoEnd,
+	UnreachableEnd,
+	UnbeatableEnd,
+	SearchGaveUp,
+	TooSmall(usize, usize),
+}
+
+impl Display for LevelIssue {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			LevelIssue::Load(error) => write!(f, "{}", error),
+			LevelIssue::NoStart => write!(f, "no start tile or start point"),
+			LevelIssue::MultipleStarts(count) => write!(f, "{} start points, the first one is used unless the manifest picks one", count),
+			LevelIssue::MissingStart(index, count) => write!(f, "the manifest picks start #{} but there are only {}", index, count),
+			LevelIssue::NoEnd => write!(f, "no end tile or end area"),
+			LevelIssue::UnreachableEnd => write!(f, "no end can be reached from the start"),
+			LevelIssue::UnbeatableEnd => write!(f, "no end can be reached with the player's movement"),
+			LevelIssue::SearchGaveUp => write!(f, "could not find a way to the end, it may not be reachable"),
+			LevelIssue::TooSmall(width, height) => write!(
+				f,
+				"{}x{} tiles is smaller than the {}x{} tile camera",
+				width,
+				height,
+				(CAMERA_WIDTH  / BLOCK_SIZE).ceil(),
+				(CAMERA_HEIGHT / BLOCK_SIZE).ceil(),
+			),
+		}
+	}
+}
+
+fn start_cell(level: &LevelData, (x, y): (f32, f32)) -> (usize, usize) {
+	(
+		(x.floor().max(0.0) as usize).min(level.width  - 1),
+		(y.floor().max(0.0) as usize).min(level.height - 1),
+	)
+}
+
+// Flood fills the open tiles around the start and checks whether any of them
+// are or border an end. This only rules out ends that are walled off entirely.
+fn end_reachable(level: &LevelData, start: (f32, f32)) -> bool {
+	let mut visited = vec![false; level.width * level.height];
+	let mut queue = VecDeque::<(usize, usize)>::new();
+	let start = start_cell(level, start);
+	if level.is_end(start.0, start.1) {
+		return true;
+	}
+	visited[start.1 * level.width + start.0] = true;
+	queue.push_back(start);
+	while let Some((x, y)) = queue.pop_front() {
+		let mut neighbors = Vec::<(usize, usize)>::with_capacity(4);
+		if x > 0                { neighbors.push((x - 1, y)); }
+		if x + 1 < level.width  { neighbors.push((x + 1, y)); }
+		if y > 0                { neighbors.push((x, y - 1)); }
+		if y + 1 < level.height { neighbors.push((x, y + 1)); }
+		for (neighbor_x, neighbor_y) in neighbors {
+			let tile = level.tile(neighbor_x, neighbor_y);
+			if level.is_end(neighbor_x, neighbor_y) {
+				return true;
+			}
+			let i = neighbor_y * level.width + neighbor_x;
+			if !tile.is_solid() && !visited[i] {
+				visited[i] = true;
+				queue.push_back((neighbor_x, neighbor_y));
+			}
+		}
+	}
+	false
+}
+
+pub fn check_data(level: &LevelData, start: Option<usize>) -> Vec<LevelIssue> {
+	let mut issues = Vec::<LevelIssue>::new();
+
+	if (level.width as f32) * BLOCK_SIZE < CAMERA_WIDTH || (level.height as f32) * BLOCK_SIZE < CAMERA_HEIGHT {
+		issues.push(LevelIssue::TooSmall(level.width, level.height));
+	}
+
+	let starts = level.start_points();
+	let start = match (starts.len(), start) {
+		(0, _) => {
+			issues.push(LevelIssue::NoStart);
+			None
+		},
+		(count, Some(index)) if index >= count => {
+			issues.push(LevelIssue::MissingStart(index, count));
+			None
+		},
+		(count, index) => {
+			if count > 1 && index.is_none() {
+				issues.push(LevelIssue::MultipleStarts(count));
+			}
+			Some(index.unwrap_or(0))
+		},
+	};
+
+	if !level.has_end() {
+		issues.push(LevelIssue::NoEnd);
+	} else if let Some(start) = start {
+		if !end_reachable(level, starts[start]) {
+			issues.push(LevelIssue::UnreachableEnd);
+		} else {
+			match reachability::solve(level, start) {
+				Reachability::Reachable(_) => {},
+				Reachability::Unreachable => issues.push(LevelIssue::UnbeatableEnd),
+				Reachability::Inconclusive => issues.push(LevelIssue::SearchGaveUp),
+			}
+		}
+	}
+
+	issues
+}
+
+// Levels are read with the same code the asset loader uses in the game, which
+// also always loads their palette.
+fn read_level(file: &Path, path: &Path, palette: &Palette) -> Result<LevelData, LevelLoadError> {
+	LevelAsset::open(file, path)?.to_data(palette)
+}
+
+pub fn check_level(path: &Path, palette_path: &Path, start: Option<usize>) -> Vec<LevelIssue> {
+	match Palette::open(palette_path).and_then(|palette| read_level(path, path, &palette)) {
+		Ok(level) => check_data(&level, start),
+		Err(error) => vec![LevelIssue::Load(error)],
+	}
+}
+
+/// Checks a level from the manifest along with its decoration layers. Their
+/// files are relative to `resources_dir`, like in the game.
+pub fn check_entry(entry: &LevelEntry, resources_dir: &Path, palette_path: &Path) -> Vec<LevelIssue> {
+	let palette = match Palette::open(palette_path) {
+		Ok(palette) => palette,
+		Err(error) => return vec![LevelIssue::Load(error)],
+	};
+	let level = match read_level(&resources_dir.join(&entry.file), Path::new(&entry.file), &palette) {
+		Ok(level) => level,
+		Err(error) => return vec![LevelIssue::Load(error)],
+	};
+	let mut issues = entry.layers
+		.iter()
+		.filter_map(|layer| LevelAsset::open(&resources_dir.join(&layer.file), Path::new(&layer.file))
+			.and_then(|asset| asset.to_layer(&palette, layer, level.width, level.height))
+			.err())
+		.map(LevelIssue::Load)
+		.collect::<Vec<_>>();
+	issues.extend(check_data(&level, entry.start));
+	issues
+}
@@ -0,0 +1,154 @@
+/*******************************************************************************
+ *
+ * Copyright (c) 2020 Gnarwhal
+ *
+ * -----------------------------------------------------------------------------
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files(the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ *
+ *******************************************************************************/
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Serialize, Deserialize};
+use crate::components::Tile;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum PropertyValue {
+	Bool(bool),
+	Int(i64),
+	Float(f64),
+	String(String),
+}
+
+pub type Properties = BTreeMap<String, PropertyValue>;
+
+// The gameplay layer is drawn at a depth of -1 and the player at 0.
+pub const BACKGROUND_DEPTH: f32 = -2.0;
+pub const FOREGROUND_DEPTH: f32 =  0.5;
+
+/// A layer of decoration the same size as the level, which the player
+/// doesn't collide with.
+#[derive(Clone, Debug)]
+pub struct LevelLayer {
+	pub name: String,
+	pub depth: f32,
+	/// How far the layer scrolls horizontally and vertically for every unit
+	/// the camera moves. 1 scrolls with the level and 0 stays put on screen.
+	pub scroll: (f32, f32),
+	pub sprites: Vec<Option<usize>>,
+}
+
+// Objects are positioned in tiles, measured from the top left of the level.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct LevelObject {
+	pub name: String,
+	pub kind: String,
+	pub x: f32,
+	pub y: f32,
+	pub width: f32,
+	pub height: f32,
+	#[serde(default)]
+	pub properties: Properties,
+}
+
+/// A rectangle in tiles, measured from the top left of the level.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct LevelArea {
+	pub x: f32,
+	pub y: f32,
+	pub width: f32,
+	pub height: f32,
+}
+
+#[derive(Clone, Debug)]
+pub struct LevelData {
+	pub width:  usize,
+	pub height: usize,
+	pub tiles:  Vec<Tile>,
+	pub sprites: Vec<Option<usize>>,
+	/// Start points placed explicitly rather than with start tiles, in tiles
+	/// from the top left of the level.
+	pub starts: Vec<(f32, f32)>,
+	/// Areas that end the level when touched, placed explicitly rather than
+	/// with end tiles. Unlike end tiles they aren't solid.
+	pub ends: Vec<LevelArea>,
+	pub objects: Vec<LevelObject>,
+	/// Custom properties of individual tiles, keyed by tile index.
+	pub properties: HashMap<usize, Properties>,
+	pub layers: Vec<LevelLayer>,
+}
+
+impl LevelData {
+	pub fn new(width: usize, height: usize, tiles: Vec<Tile>, sprites: Vec<Option<usize>>) -> Self {
+		LevelData {
+			width,
+			height,
+			tiles,
+			sprites,
+			starts: vec![],
+			ends: vec![],
+			objects: vec![],
+			properties: HashMap::new(),
+			layers: vec![],
+		}
+	}
+
+	pub fn tile(&self, x: usize, y: usize) -> Tile {
+		self.tiles[y * self.width + x]
+	}
+
+	pub fn has_end(&self) -> bool {
+		!self.ends.is_empty() || self.tiles.contains(&Tile::End)
+	}
+
+	/// Whether the tile is an end tile or overlaps one of the end areas.
+	pub fn is_end(&self, x: usize, y: usize) -> bool {
+		self.tile(x, y) == Tile::End || self.ends.iter().any(|end| {
+			end.x < (x + 1) as f32 && end.x + end.width > x as f32
+			&& end.y < (y + 1) as f32 && end.y + end.height > y as f32
+		})
+	}
+
+	// Explicit start points come first, followed by the start tiles.
+	// Horizontally adjacent start tiles form a single start point where the
+	// player stands on top of the middle of the group, and groups are ordered
+	// top to bottom, left to right.
+	pub fn start_points(&self) -> Vec<(f32, f32)> {
+		let mut runs = Vec::<(usize, usize, usize)>::new();
+		for y in 0..self.height {
+			for x in 0..self.width {
+				if self.tile(x, y) != Tile::Start {
+					continue;
+				}
+				match runs.last_mut() {
+					Some((_, last, row)) if *row == y && *last + 1 == x => { *last = x; },
+					_ => runs.push((x, x, y)),
+				}
+			}
+		}
+		self.starts
+			.iter()
+			.copied()
+			.chain(runs
+				.into_iter()
+				.map(|(first, last, row)| ((first + last + 1) as f32 / 2.0, row as f32 - 0.5)))
+			.collect()
+	}
+}
@@ -0,0 +1,177 @@
+/*******************************************************************************
+ *
+ * Copyright (c) 2020 Gnarwhal
+ *
+ * -----------------------------------------------------------------------------
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files(the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ *
+ *******************************************************************************/
+
+use std::{
+	error::Error,
+	fmt::{self, Display},
+	io,
+	path::PathBuf,
+};
+
+use amethyst::config::ConfigError;
+use image::ImageError;
+use crate::components::Tile;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InvalidPixel {
+	pub x: u32,
+	pub y: u32,
+	pub color: [u8; 4],
+}
+
+impl Display for InvalidPixel {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let [r, g, b, a] = self.color;
+		write!(f, "({}, {}) has color rgba({}, {}, {}, {})", self.x, self.y, r, g, b, a)
+	}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InvalidCharacter {
+	pub x: usize,
+	pub y: usize,
+	pub character: char,
+}
+
+impl Display for InvalidCharacter {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "({}, {}) has character {:?}", self.x, self.y, self.character)
+	}
+}
+
+#[derive(Debug)]
+pub enum LevelLoadError {
+	Missing(PathBuf),
+	/// The asset loader failed to load the file, with its error message.
+	Asset(PathBuf, String),
+	Io(PathBuf, io::Error),
+	Decode(PathBuf, ImageError),
+	InvalidPixels(PathBuf, Vec<InvalidPixel>),
+	Text(PathBuf, ConfigError),
+	RaggedRow(PathBuf, usize, usize, usize),
+	InvalidCharacters(PathBuf, Vec<InvalidCharacter>),
+	Tiled(PathBuf, String),
+	UnknownFormat(PathBuf),
+	NoStart(PathBuf),
+	MissingStart(PathBuf, usize),
+	Palette(PathBuf, ConfigError),
+	LayerSize(PathBuf, (usize, usize), (usize, usize)),
+}
+
+impl LevelLoadError {
+	pub fn from_image(path: PathBuf, error: ImageError) -> Self {
+		match error {
+			ImageError::IoError(error) if error.kind() == io::ErrorKind::NotFound => LevelLoadError::Missing(path),
+			ImageError::IoError(error) => LevelLoadError::Io(path, error),
+			error => LevelLoadError::Decode(path, error),
+		}
+	}
+}
+
+impl Display for LevelLoadError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			LevelLoadError::Missing(path) => write!(f, "'{}' does not exist", path.display()),
+			LevelLoadError::Asset(path, error) => write!(f, "could not load '{}': {}", path.display(), error),
+			LevelLoadError::Io(path, error) => write!(f, "could not read '{}': {}", path.display(), error),
+			LevelLoadError::Decode(path, error) => write!(f, "could not decode '{}': {}", path.display(), error),
+			LevelLoadError::InvalidPixels(path, pixels) => {
+				write!(f, "'{}' has {} invalid tile color(s):", path.display(), pixels.len())?;
+				for pixel in pixels {
+					write!(f, "\n\t{}", pixel)?;
+				}
+				Ok(())
+			},
+			LevelLoadError::Text(path, error) => write!(f, "could not parse '{}': {}", path.display(), error),
+			LevelLoadError::RaggedRow(path, row, length, expected) => write!(f, "'{}' row {} is {} tiles long, expected {}", path.display(), row, length, expected),
+			LevelLoadError::InvalidCharacters(path, characters) => {
+				write!(f, "'{}' has {} invalid tile character(s):", path.display(), characters.len())?;
+				for character in characters {
+					write!(f, "\n\t{}", character)?;
+				}
+				Ok(())
+			},
+			LevelLoadError::Tiled(path, error) => write!(f, "could not read Tiled map '{}': {}", path.display(), error),
+			LevelLoadError::UnknownFormat(path) => write!(f, "'{}' is not a .png, .ron, .tmx or .tmj level", path.display()),
+			LevelLoadError::NoStart(path) => write!(f, "'{}' has no start tile or start point", path.display()),
+			LevelLoadError::MissingStart(path, index) => write!(f, "'{}' has no start #{}", path.display(), index),
+			LevelLoadError::Palette(path, error) => write!(f, "could not load palette '{}': {}", path.display(), error),
+			LevelLoadError::LayerSize(path, (width, height), (expected_width, expected_height)) => write!(f, "layer '{}' is {}x{} tiles, expected {}x{} like its level", path.display(), width, height, expected_width, expected_height),
+		}
+	}
+}
+
+impl Error for LevelLoadError {
+	fn source(&self) -> Option<&(dyn Error + 'static)> {
+		match self {
+			LevelLoadError::Io(_, error) => Some(error),
+			LevelLoadError::Decode(_, error) => Some(error),
+			LevelLoadError::Text(_, error) => Some(error),
+			LevelLoadError::Palette(_, error) => Some(error),
+			_ => None,
+		}
+	}
+}
+
+#[derive(Debug)]
+pub enum LevelSaveError {
+	NoColor(Tile, Option<usize>),
+	TooManyTiles(usize),
+	Empty,
+	Palette(PathBuf, ConfigError),
+	Image(PathBuf, ImageError),
+	Text(PathBuf, ConfigError),
+	UnknownFormat(PathBuf),
+	/// The format has nowhere to put part of the level.
+	Unsupported(PathBuf, &'static str),
+}
+
+impl Display for LevelSaveError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			LevelSaveError::NoColor(tile, Some(sprite)) => write!(f, "the palette has no color for {:?} with sprite {}", tile, sprite),
+			LevelSaveError::NoColor(tile, None) => write!(f, "the palette has no color for {:?} without a sprite", tile),
+			LevelSaveError::TooManyTiles(count) => write!(f, "the level has {} distinct tiles, which is too many to give each a character", count),
+			LevelSaveError::Empty => write!(f, "the level has no tiles"),
+			LevelSaveError::Palette(path, error) => write!(f, "could not load palette '{}': {}", path.display(), error),
+			LevelSaveError::Image(path, error) => write!(f, "could not write '{}': {}", path.display(), error),
+			LevelSaveError::Text(path, error) => write!(f, "could not write '{}': {}", path.display(), error),
+			LevelSaveError::UnknownFormat(path) => write!(f, "'{}' is not a .png or .ron level", path.display()),
+			LevelSaveError::Unsupported(path, what) => write!(f, "'{}' can't hold the level's {}, save it as a .ron level instead", path.display(), what),
+		}
+	}
+}
+
+impl Error for LevelSaveError {
+	fn source(&self) -> Option<&(dyn Error + 'static)> {
+		match self {
+			LevelSaveError::Palette(_, error) => Some(error),
+			LevelSaveError::Image(_, error) => Some(error),
+			LevelSaveError::Text(_, error) => Some(error),
+			_ => None,
+		}
+	}
+}
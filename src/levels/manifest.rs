@@ -0,0 +1,95 @@
+/*******************************************************************************
+ *
+ * Copyright (c) 2020 Gnarwhal
+ *
+ * -----------------------------------------------------------------------------
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files(the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ *
+ *******************************************************************************/
+
+use serde::{Serialize, Deserialize};
+use crate::levels::BACKGROUND_DEPTH;
+
+pub const DEFAULT_PALETTE: &str = "levels/palette.ron";
+
+/// A decoration layer read from its own level file. Only the sprites of the
+/// file are used, read with the level's palette.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LayerEntry {
+	pub file: String,
+	/// Defaults to `BACKGROUND_DEPTH`.
+	#[serde(default)]
+	pub depth: Option<f32>,
+	/// Horizontal and vertical scroll factors for parallax. Defaults to
+	/// scrolling with the level.
+	#[serde(default)]
+	pub scroll: Option<(f32, f32)>,
+}
+
+impl LayerEntry {
+	pub fn depth(&self) -> f32 {
+		self.depth.unwrap_or(BACKGROUND_DEPTH)
+	}
+
+	pub fn scroll(&self) -> (f32, f32) {
+		self.scroll.unwrap_or((1.0, 1.0))
+	}
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LevelEntry {
+	pub name: String,
+	pub file: String,
+	/// Which start point to spawn at when the level has more than one,
+	/// counted top to bottom, left to right. Defaults to the first.
+	#[serde(default)]
+	pub start: Option<usize>,
+	/// Color palette used to read the level bitmap. Defaults to
+	/// `DEFAULT_PALETTE`.
+	#[serde(default)]
+	pub palette: Option<String>,
+	#[serde(default)]
+	pub layers: Vec<LayerEntry>,
+}
+
+impl LevelEntry {
+	pub fn palette(&self) -> &str {
+		self.palette.as_deref().unwrap_or(DEFAULT_PALETTE)
+	}
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct LevelManifest {
+	pub levels: Vec<LevelEntry>,
+}
+
+impl LevelManifest {
+	pub fn get(&self, index: usize) -> Option<&LevelEntry> {
+		self.levels.get(index)
+	}
+
+	pub fn len(&self) -> usize {
+		self.levels.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.levels.is_empty()
+	}
+}
@@ -0,0 +1,117 @@
+/*******************************************************************************
+ *
+ * Copyright (c) 2020 Gnarwhal
+ *
+ * -----------------------------------------------------------------------------
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files(the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ *
+ *******************************************************************************/
+
+use std::path::Path;
+
+use amethyst::config::Config;
+
+pub use self::{
+	asset::LevelAsset,
+	asset::LevelFormat,
+	asset::LevelSource,
+
+	autotile::AutoTileRule,
+	autotile::AutoTileRules,
+	autotile::Connectivity,
+
+	check::LevelIssue,
+
+	data::BACKGROUND_DEPTH,
+	data::FOREGROUND_DEPTH,
+	data::LevelArea,
+	data::LevelData,
+	data::LevelLayer,
+	data::LevelObject,
+	data::Properties,
+	data::PropertyValue,
+
+	error::InvalidCharacter,
+	error::InvalidPixel,
+	error::LevelLoadError,
+	error::LevelSaveError,
+
+	manifest::DEFAULT_PALETTE,
+	manifest::LayerEntry,
+	manifest::LevelManifest,
+	manifest::LevelEntry,
+
+	palette::Palette,
+	palette::PaletteEntry,
+
+	reachability::Reachability,
+
+	text::LegendEntry,
+	text::TextLevel,
+};
+pub mod asset;
+pub mod autotile;
+pub mod bitmap;
+pub mod check;
+pub mod data;
+pub mod error;
+pub mod manifest;
+pub mod palette;
+pub mod reachability;
+pub mod text;
+pub mod tiled;
+
+fn extension(path: &Path) -> Option<String> {
+	path.extension().map(|extension| extension.to_string_lossy().to_lowercase())
+}
+
+// Reads a level in any supported format. The palette is only used by
+// bitmap levels; text levels carry their own legend.
+pub fn read_level(path: &Path, palette_path: &Path) -> Result<LevelData, LevelLoadError> {
+	match extension(path).as_deref() {
+		Some("png") => bitmap::load(path, &Palette::open(palette_path)?),
+		Some("ron") => text::load(path),
+		Some("tmx") => tiled::load_tmx(path),
+		Some("tmj") | Some("json") => tiled::load_tmj(path),
+		_ => Err(LevelLoadError::UnknownFormat(path.to_owned())),
+	}
+}
+
+pub fn write_level(level: &LevelData, path: &Path, palette_path: &Path) -> Result<(), LevelSaveError> {
+	match extension(path).as_deref() {
+		Some("png") => {
+			let palette = Palette::load(palette_path).map_err(|error| LevelSaveError::Palette(palette_path.to_owned(), error))?;
+			bitmap::save(level, &palette, path)
+		},
+		Some("ron") => text::save(level, path),
+		_ => Err(LevelSaveError::UnknownFormat(path.to_owned())),
+	}
+}
+
+fn require_start(level: LevelData, path: &Path) -> Result<LevelData, LevelLoadError> {
+	if level.start_points().is_empty() {
+		return Err(LevelLoadError::NoStart(path.to_owned()));
+	}
+	Ok(level)
+}
+
+pub fn load_level(path: &Path, palette_path: &Path) -> Result<LevelData, LevelLoadError> {
+	require_start(read_level(path, palette_path)?, path)
+}
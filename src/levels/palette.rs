@@ -0,0 +1,84 @@
+/*******************************************************************************
+ *
+ * Copyright (c) 2020 Gnarwhal
+ *
+ * -----------------------------------------------------------------------------
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files(the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ *
+ *******************************************************************************/
+
+use std::{
+	collections::HashMap,
+	path::Path,
+};
+
+use amethyst::{
+	assets::{Asset, Handle},
+	config::Config,
+	ecs::VecStorage,
+};
+use serde::{Serialize, Deserialize};
+use crate::components::Tile;
+use crate::levels::LevelLoadError;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PaletteEntry {
+	pub color: [u8; 4],
+	pub tile: Tile,
+	#[serde(default)]
+	pub sprite: Option<usize>,
+	/// The sprite drawn instead once the tile is activated, like a touched
+	/// checkpoint.
+	#[serde(default)]
+	pub active_sprite: Option<usize>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Palette {
+	pub entries: Vec<PaletteEntry>,
+}
+
+impl Asset for Palette {
+	const NAME: &'static str = "djam_4::Palette";
+	type Data = Self;
+	type HandleStorage = VecStorage<Handle<Self>>;
+}
+
+impl Palette {
+	pub fn open(path: &Path) -> Result<Self, LevelLoadError> {
+		Palette::load(path).map_err(|error| LevelLoadError::Palette(path.to_owned(), error))
+	}
+
+	pub fn lookup(&self, color: [u8; 4]) -> Option<&PaletteEntry> {
+		self.entries.iter().find(|entry| entry.color == color)
+	}
+
+	/// The active sprite of each tile and sprite that has one.
+	pub fn active_sprites(&self) -> HashMap<(Tile, Option<usize>), usize> {
+		self.entries
+			.iter()
+			.filter_map(|entry| entry.active_sprite.map(|active| ((entry.tile, entry.sprite), active)))
+			.collect()
+	}
+
+	pub fn reverse_lookup(&self, tile: Tile, sprite: Option<usize>) -> Option<&PaletteEntry> {
+		self.entries.iter().find(|entry| entry.tile == tile && entry.sprite == sprite)
+	}
+}
@@ -0,0 +1,240 @@
+/*******************************************************************************
+ *
+ * Copyright (c) 2020 Gnarwhal
+ *
+ * -----------------------------------------------------------------------------
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files(the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ *
+ *******************************************************************************/
+
+use std::collections::{HashSet, VecDeque};
+
+use amethyst::core::math::Vector3;
+use crate::components::{Collider, Dynamic, Player, Tile};
+use crate::levels::LevelData;
+use crate::states::level::{Level, BLOCK_SIZE};
+use crate::systems::level::{touched_hazard, touches_end};
+use crate::systems::physics::{apply_forces, move_and_collide, PHYSICS_STEP};
+use crate::systems::player::{move_player, PlayerInput};
+
+// The search picks a new input every few steps, which is about as often as a
+// person can change what they are pressing
+const STEPS_PER_INPUT: usize = 6;
+
+// How many inputs to try before giving up on a level. Ruling out a single
+// pit that is too wide to jump already takes about a million
+const SEARCH_LIMIT: usize = 4_000_000;
+
+// States closer than this are treated as the same state
+const POSITION_RESOLUTION: f32 = BLOCK_SIZE / 4.0;
+const VELOCITY_RESOLUTION: f32 = BLOCK_SIZE;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Reachability {
+	/// The end can be reached along the first path the search found, which
+	/// takes this many seconds. Merging nearby states means a quicker path
+	/// may still exist
+	Reachable(f32),
+	Unreachable,
+	/// The search ran out of states to try before finding the end
+	Inconclusive,
+}
+
+#[derive(Clone)]
+struct Body {
+	position: Vector3<f32>,
+	dynamic: Dynamic,
+	player: Player,
+}
+
+type StateKey = (i32, i32, i32, i32, bool, bool, usize);
+
+impl Body {
+	fn key(&self) -> StateKey {
+		(
+			(self.position.x / POSITION_RESOLUTION).round() as i32,
+			(self.position.y / POSITION_RESOLUTION).round() as i32,
+			(self.dynamic.velocity.x / VELOCITY_RESOLUTION).round() as i32,
+			(self.dynamic.velocity.y / VELOCITY_RESOLUTION).round() as i32,
+			self.dynamic.grounded,
+			self.player.jump_ready,
+			self.player.jump_count,
+		)
+	}
+
+	fn in_bounds(&self, level: &Level) -> bool {
+		self.position.x >= level.left
+		&& self.position.x <= level.right
+		&& self.position.y >= level.bottom
+		&& self.position.y <= level.top
+	}
+}
+
+fn inputs(level: &Level) -> Vec<PlayerInput> {
+	// Dropping through is the same as standing still without platforms
+	let drop_through = level.tiles.contains(&Tile::Platform);
+	let mut inputs = Vec::<PlayerInput>::with_capacity(12);
+	for &movement in &[-1.0, 0.0, 1.0] {
+		for &(short_hop, full_hop) in &[(false, false), (true, false), (false, true)] {
+			inputs.push(PlayerInput { movement, short_hop, full_hop, drop_through: false });
+		}
+		if drop_through {
+			inputs.push(PlayerInput { movement, drop_through: true, ..PlayerInput::default() });
+		}
+	}
+	inputs
+}
+
+enum Outcome {
+	Reached,
+	Died,
+	Moving,
+}
+
+// Runs the same steps as the force, player movement, collision and hazard
+// systems, stopping early if the body touches an end or dies.
+fn simulate(body: &mut Body, input: &PlayerInput, level: &Level) -> Outcome {
+	let collider = Collider::default();
+	for _ in 0..STEPS_PER_INPUT {
+		apply_forces(&mut body.dynamic, true, PHYSICS_STEP);
+		move_player(&mut body.dynamic, &mut body.player, input, PHYSICS_STEP);
+		move_and_collide(&mut body.position, &mut body.dynamic, &collider, level, PHYSICS_STEP);
+		if touched_hazard(&body.position, &collider, level).is_some() {
+			return Outcome::Died;
+		}
+		if touches_end(&body.position, &collider, level) {
+			return Outcome::Reached;
+		}
+	}
+	Outcome::Moving
+}
+
+/// Searches for a way from the given start point to any end by playing
+/// the level with the game's own movement and collision code.
+pub fn solve(level_data: &LevelData, start: usize) -> Reachability {
+	let level = Level::new(level_data);
+	let start = match level.starts.get(start) {
+		Some(start) => start,
+		None => return Reachability::Unreachable,
+	};
+	let inputs = inputs(&level);
+
+	let body = Body {
+		position: Vector3::new(start.x, start.y, 0.0),
+		dynamic: Dynamic::default(),
+		player: Player::default(),
+	};
+	let mut visited = HashSet::<StateKey>::new();
+	let mut queue = VecDeque::<(Body, usize)>::new();
+	visited.insert(body.key());
+	queue.push_back((body, 0));
+
+	let mut searched = 0;
+	while let Some((body, depth)) = queue.pop_front() {
+		for input in &inputs {
+			if searched == SEARCH_LIMIT {
+				return Reachability::Inconclusive;
+			}
+			searched += 1;
+
+			let mut next = body.clone();
+			match simulate(&mut next, input, &level) {
+				Outcome::Reached => return Reachability::Reachable((depth + 1) as f32 * STEPS_PER_INPUT as f32 * PHYSICS_STEP),
+				Outcome::Died => {},
+				Outcome::Moving => if next.in_bounds(&level) && visited.insert(next.key()) {
+					queue.push_back((next, depth + 1));
+				},
+			}
+		}
+	}
+	Reachability::Unreachable
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::components::Tile;
+	use crate::levels::LevelData;
+	use crate::states::level::BLOCK_SIZE;
+	use crate::systems::physics::GRAVITY;
+	use crate::systems::player::{AERIAL_HOP_HEIGHT, FULL_HOP_HEIGHT, FULL_HOP_TIME, JUMP_COUNT, MAX_AERIAL_SPEED};
+	use super::{solve, Reachability};
+
+	// '#' is ground, 'S' and 'E' are the start and end and anything else is
+	// background. The player stands on top of the start tile.
+	fn level(rows: &[String]) -> LevelData {
+		let width = rows[0].len();
+		let height = rows.len();
+		let tiles = rows
+			.iter()
+			.flat_map(|row| row.chars())
+			.map(|character| match character {
+				'#' => Tile::Ground,
+				'S' => Tile::Start,
+				'E' => Tile::End,
+				_   => Tile::Background,
+			})
+			.collect();
+		LevelData::new(width, height, tiles, vec![None; width * height])
+	}
+
+	// A wall between the start and the end, rising `rise` blocks above
+	// where the player starts
+	fn wall(rise: usize) -> LevelData {
+		let mut rows = vec![".......".to_owned(); 4];
+		rows.extend((0..rise).map(|_| "...#...".to_owned()));
+		rows.push(".S.#.E.".to_owned());
+		rows.push("#######".to_owned());
+		level(&rows)
+	}
+
+	// A pit `width` blocks wide between the start and the end
+	fn gap(width: usize) -> LevelData {
+		let pit = ".".repeat(width);
+		let mut rows = vec![format!("...{}...", pit); 4];
+		rows.push(format!(".S.{}.E.", pit));
+		rows.push(format!("###{}###", pit));
+		level(&rows)
+	}
+
+	#[test]
+	fn one_jump_clears_a_low_wall() {
+		assert!(matches!(solve(&wall(2), 0), Reachability::Reachable(_)));
+	}
+
+	#[test]
+	fn second_jump_clears_a_high_wall() {
+		let rise = (FULL_HOP_HEIGHT / BLOCK_SIZE).ceil();
+		assert!(JUMP_COUNT > 1 && rise * BLOCK_SIZE <= FULL_HOP_HEIGHT + AERIAL_HOP_HEIGHT);
+		assert!(matches!(solve(&wall(rise as usize), 0), Reachability::Reachable(_)));
+	}
+
+	#[test]
+	fn gap_beyond_aerial_reach_is_unreachable() {
+		// The longest the player can stay in the air is a full hop with every
+		// aerial hop saved until just before landing
+		let aerial_hop_time = (2.0 * AERIAL_HOP_HEIGHT / -GRAVITY).sqrt();
+		let airtime = 2.0 * (FULL_HOP_TIME + (JUMP_COUNT - 1) as f32 * aerial_hop_time);
+		let reach = MAX_AERIAL_SPEED * airtime / BLOCK_SIZE;
+		// The player only has to overlap the ground on either side, so
+		// crossing a pit takes one block less than its width
+		let width = reach.floor() as usize + 2;
+		assert_eq!(solve(&gap(width), 0), Reachability::Unreachable);
+	}
+}
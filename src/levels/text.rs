@@ -0,0 +1,220 @@
+/*******************************************************************************
+ *
+ * Copyright (c) 2020 Gnarwhal
+ *
+ * -----------------------------------------------------------------------------
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files(the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ *
+ *******************************************************************************/
+
+use std::{
+	collections::{BTreeMap, HashMap, HashSet},
+	path::Path,
+};
+
+use amethyst::config::Config;
+use serde::{Serialize, Deserialize};
+use crate::components::Tile;
+use crate::levels::{InvalidCharacter, LevelArea, LevelData, LevelLoadError, LevelObject, LevelSaveError, Properties};
+
+// Characters tried in order when picking a legend character for a tile that
+// has no conventional one, or whose conventional one is already taken.
+const SPARE_CHARACTERS: &str = "abcdefghijklmnopqrstuvwxyzABDFGHIJKLMNOPQRTUVWXYZ0123456789";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LegendEntry {
+	pub tile: Tile,
+	#[serde(default)]
+	pub sprite: Option<usize>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct TextLevel {
+	pub legend: BTreeMap<char, LegendEntry>,
+	pub rows: Vec<String>,
+	/// Everything else only Tiled can place, kept so that converting a Tiled
+	/// map doesn't lose it. Written out only when there is any.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub starts: Vec<(f32, f32)>,
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub ends: Vec<LevelArea>,
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub objects: Vec<LevelObject>,
+	/// Custom tile properties, keyed by tile index from the top left.
+	#[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+	pub properties: BTreeMap<usize, Properties>,
+}
+
+fn conventional_character(tile: Tile) -> char {
+	match tile {
+		Tile::Background           => '.',
+		Tile::Ground               => '#',
+		Tile::Start                => 'S',
+		Tile::End                  => 'E',
+		Tile::Ice                  => '-',
+		Tile::Mud                  => '~',
+		Tile::Bounce               => '^',
+		Tile::Platform             => '_',
+		Tile::Spikes               => '*',
+		Tile::Lava                 => '=',
+		Tile::Checkpoint           => 'C',
+		Tile::SlopeRight           => '/',
+		Tile::SlopeLeft            => '\\',
+		Tile::GentleSlopeRightLow  => ',',
+		Tile::GentleSlopeRightHigh => '\'',
+		Tile::GentleSlopeLeftLow   => ';',
+		Tile::GentleSlopeLeftHigh  => '`',
+	}
+}
+
+impl TextLevel {
+	pub fn from_level(level: &LevelData) -> Result<Self, LevelSaveError> {
+		if level.width == 0 || level.height == 0 {
+			return Err(LevelSaveError::Empty);
+		}
+		let mut legend = BTreeMap::<char, LegendEntry>::new();
+		let mut characters = HashMap::<(Tile, Option<usize>), char>::new();
+		let mut spare = SPARE_CHARACTERS.chars();
+		for (&tile, &sprite) in level.tiles.iter().zip(&level.sprites) {
+			if characters.contains_key(&(tile, sprite)) {
+				continue;
+			}
+			let mut character = conventional_character(tile);
+			while legend.contains_key(&character) {
+				character = match spare.next() {
+					Some(character) => character,
+					None => {
+						let distinct = level.tiles
+							.iter()
+							.zip(&level.sprites)
+							.collect::<HashSet<_>>()
+							.len();
+						return Err(LevelSaveError::TooManyTiles(distinct));
+					},
+				};
+			}
+			legend.insert(character, LegendEntry { tile, sprite });
+			characters.insert((tile, sprite), character);
+		}
+		let rows = level.tiles
+			.chunks(level.width)
+			.zip(level.sprites.chunks(level.width))
+			.map(|(tiles, sprites)| tiles
+				.iter()
+				.zip(sprites)
+				.map(|(&tile, &sprite)| characters[&(tile, sprite)])
+				.collect())
+			.collect();
+		Ok(TextLevel {
+			legend,
+			rows,
+			starts: level.starts.clone(),
+			ends: level.ends.clone(),
+			objects: level.objects.clone(),
+			properties: level.properties
+				.iter()
+				.map(|(&index, properties)| (index, properties.clone()))
+				.collect(),
+		})
+	}
+
+	pub fn to_level(&self, path: &Path) -> Result<LevelData, LevelLoadError> {
+		let width = self.rows.first().map(|row| row.chars().count()).unwrap_or(0);
+		let height = self.rows.len();
+		let mut tiles = Vec::<Tile>::with_capacity(width * height);
+		let mut sprites = Vec::<Option<usize>>::with_capacity(width * height);
+		let mut invalid_characters = Vec::<InvalidCharacter>::new();
+		for (y, row) in self.rows.iter().enumerate() {
+			let length = row.chars().count();
+			if length != width {
+				return Err(LevelLoadError::RaggedRow(path.to_owned(), y, length, width));
+			}
+			for (x, character) in row.chars().enumerate() {
+				match self.legend.get(&character) {
+					Some(entry) => {
+						tiles.push(entry.tile);
+						sprites.push(entry.sprite);
+					},
+					None => invalid_characters.push(InvalidCharacter { x, y, character }),
+				}
+			}
+		}
+		if !invalid_characters.is_empty() {
+			return Err(LevelLoadError::InvalidCharacters(path.to_owned(), invalid_characters));
+		}
+		let mut level = LevelData::new(width, height, tiles, sprites);
+		level.starts = self.starts.clone();
+		level.ends = self.ends.clone();
+		level.objects = self.objects.clone();
+		level.properties = self.properties
+			.iter()
+			.filter(|(&index, _)| index < width * height)
+			.map(|(&index, properties)| (index, properties.clone()))
+			.collect();
+		Ok(level)
+	}
+}
+
+pub fn from_bytes(bytes: &[u8], path: &Path) -> Result<LevelData, LevelLoadError> {
+	TextLevel::load_bytes(bytes)
+		.map_err(|error| LevelLoadError::Text(path.to_owned(), error))?
+		.to_level(path)
+}
+
+pub fn load(path: &Path) -> Result<LevelData, LevelLoadError> {
+	TextLevel::load(path)
+		.map_err(|error| LevelLoadError::Text(path.to_owned(), error))?
+		.to_level(path)
+}
+
+pub fn save(level: &LevelData, path: &Path) -> Result<(), LevelSaveError> {
+	TextLevel::from_level(level)?
+		.write(path)
+		.map_err(|error| LevelSaveError::Text(path.to_owned(), error))
+}
+
+#[cfg(test)]
+mod tests {
+	use std::path::Path;
+
+	use crate::components::Tile;
+	use crate::levels::{LevelArea, LevelData, LevelObject, Properties, PropertyValue};
+	use super::TextLevel;
+
+	#[test]
+	fn tiled_only_parts_survive_a_round_trip() {
+		let mut level = LevelData::new(2, 1, vec![Tile::Background, Tile::Ice], vec![None, Some(1)]);
+		level.starts = vec![(0.5, 0.5)];
+		level.ends = vec![LevelArea { x: 1.0, y: 0.0, width: 1.0, height: 1.0 }];
+		level.objects = vec![LevelObject { name: "bonus".to_owned(), kind: "coin".to_owned(), ..LevelObject::default() }];
+		let mut properties = Properties::new();
+		properties.insert("friction".to_owned(), PropertyValue::Float(0.5));
+		level.properties.insert(1, properties);
+
+		let read = TextLevel::from_level(&level).unwrap().to_level(Path::new("test.ron")).unwrap();
+		assert_eq!(read.tiles, level.tiles);
+		assert_eq!(read.sprites, level.sprites);
+		assert_eq!(read.starts, level.starts);
+		assert_eq!(read.ends, level.ends);
+		assert_eq!(read.objects.len(), 1);
+		assert_eq!(read.objects[0].kind, "coin");
+		assert_eq!(read.properties, level.properties);
+	}
+}
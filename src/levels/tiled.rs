@@ -0,0 +1,775 @@
+/*******************************************************************************
+ *
+ * Copyright (c) 2020 Gnarwhal
+ *
+ * -----------------------------------------------------------------------------
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files(the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ *
+ *******************************************************************************/
+
+use std::{
+	collections::HashMap,
+	fs,
+	io,
+	path::Path,
+	str::{self, FromStr},
+};
+
+use roxmltree::Node;
+use serde::Deserialize;
+use serde_json::Value;
+use crate::components::Tile;
+use crate::levels::{BACKGROUND_DEPTH, FOREGROUND_DEPTH, LevelArea, LevelData, LevelLayer, LevelLoadError, LevelObject, Properties, PropertyValue};
+
+// The top bits of a gid mark the tile as flipped, which doesn't matter to us
+const FLIP_FLAGS: u32 = 0xE000_0000;
+
+const KIND_PROPERTY:   &str = "kind";
+const SPRITE_PROPERTY: &str = "sprite";
+
+const GAMEPLAY_LAYER: &str = "gameplay";
+const DEPTH_PROPERTY: &str = "depth";
+
+const START_OBJECT: &str = "start";
+const END_OBJECT:   &str = "end";
+
+//////// Common map representation for both .tmx and .tmj ////////
+
+struct Tileset {
+	first_gid: u32,
+	tiles: HashMap<u32, Properties>,
+}
+
+struct Object {
+	name: String,
+	kind: String,
+	x: f32,
+	y: f32,
+	width: f32,
+	height: f32,
+	gid: Option<u32>,
+	properties: Properties,
+}
+
+struct TileLayer {
+	name: String,
+	data: Vec<u32>,
+	properties: Properties,
+	parallax: (f32, f32),
+}
+
+enum Layer {
+	Tiles(TileLayer),
+	Objects(Vec<Object>),
+	Other,
+}
+
+struct Map {
+	width: usize,
+	height: usize,
+	tile_width: f32,
+	tile_height: f32,
+	tilesets: Vec<Tileset>,
+	layers: Vec<Layer>,
+}
+
+//////// .tmj ////////
+
+#[derive(Deserialize)]
+struct JsonProperty {
+	name: String,
+	#[serde(default, rename = "type")]
+	kind: String,
+	value: Value,
+}
+
+#[derive(Deserialize)]
+struct JsonTile {
+	id: u32,
+	#[serde(default)]
+	properties: Vec<JsonProperty>,
+}
+
+#[derive(Deserialize)]
+struct JsonTileset {
+	firstgid: u32,
+	#[serde(default)]
+	source: Option<String>,
+	#[serde(default)]
+	tiles: Vec<JsonTile>,
+}
+
+#[derive(Deserialize)]
+struct JsonObject {
+	#[serde(default)]
+	name: String,
+	#[serde(default, rename = "type")]
+	kind: String,
+	#[serde(default)]
+	class: String,
+	x: f32,
+	y: f32,
+	#[serde(default)]
+	width: f32,
+	#[serde(default)]
+	height: f32,
+	#[serde(default)]
+	gid: Option<u32>,
+	#[serde(default)]
+	properties: Vec<JsonProperty>,
+}
+
+fn default_parallax() -> f32 {
+	1.0
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+enum JsonLayer {
+	#[serde(rename = "tilelayer")]
+	Tiles {
+		#[serde(default)]
+		name: String,
+		data: Vec<u32>,
+		#[serde(default)]
+		properties: Vec<JsonProperty>,
+		#[serde(default = "default_parallax")]
+		parallaxx: f32,
+		#[serde(default = "default_parallax")]
+		parallaxy: f32,
+	},
+	#[serde(rename = "objectgroup")]
+	Objects {
+		objects: Vec<JsonObject>,
+	},
+	#[serde(other)]
+	Other,
+}
+
+#[derive(Deserialize)]
+struct JsonMap {
+	width: usize,
+	height: usize,
+	tilewidth: f32,
+	tileheight: f32,
+	#[serde(default)]
+	tilesets: Vec<JsonTileset>,
+	layers: Vec<JsonLayer>,
+}
+
+fn json_properties(properties: Vec<JsonProperty>) -> Properties {
+	properties
+		.into_iter()
+		.map(|property| {
+			let value = match (property.kind.as_str(), property.value) {
+				(_, Value::Bool(value)) => PropertyValue::Bool(value),
+				("float", Value::Number(value)) => PropertyValue::Float(value.as_f64().unwrap_or(0.0)),
+				(_, Value::Number(value)) => match value.as_i64() {
+					Some(value) => PropertyValue::Int(value),
+					None => PropertyValue::Float(value.as_f64().unwrap_or(0.0)),
+				},
+				(_, Value::String(value)) => PropertyValue::String(value),
+				(_, value) => PropertyValue::String(value.to_string()),
+			};
+			(property.name, value)
+		})
+		.collect()
+}
+
+fn parse_tmj(text: &str) -> Result<Map, String> {
+	let map: JsonMap = serde_json::from_str(text).map_err(|error| error.to_string())?;
+	let mut tilesets = Vec::<Tileset>::with_capacity(map.tilesets.len());
+	for tileset in map.tilesets {
+		if let Some(source) = tileset.source {
+			return Err(format!("external tileset '{}' is not supported, embed it in the map", source));
+		}
+		tilesets.push(Tileset {
+			first_gid: tileset.firstgid,
+			tiles: tileset.tiles
+				.into_iter()
+				.map(|tile| (tile.id, json_properties(tile.properties)))
+				.collect(),
+		});
+	}
+	let layers = map.layers
+		.into_iter()
+		.map(|layer| match layer {
+			JsonLayer::Tiles { name, data, properties, parallaxx, parallaxy } => Layer::Tiles(TileLayer {
+				name,
+				data,
+				properties: json_properties(properties),
+				parallax: (parallaxx, parallaxy),
+			}),
+			JsonLayer::Objects { objects } => Layer::Objects(objects
+				.into_iter()
+				.map(|object| Object {
+					name: object.name,
+					kind: if object.class.is_empty() { object.kind } else { object.class },
+					x: object.x,
+					y: object.y,
+					width: object.width,
+					height: object.height,
+					gid: object.gid,
+					properties: json_properties(object.properties),
+				})
+				.collect()),
+			JsonLayer::Other => Layer::Other,
+		})
+		.collect();
+	Ok(Map {
+		width: map.width,
+		height: map.height,
+		tile_width: map.tilewidth,
+		tile_height: map.tileheight,
+		tilesets,
+		layers,
+	})
+}
+
+//////// .tmx ////////
+
+fn attribute<T: FromStr>(node: Node, name: &str) -> Result<T, String> {
+	node.attribute(name)
+		.ok_or_else(|| format!("<{}> is missing '{}'", node.tag_name().name(), name))?
+		.parse()
+		.map_err(|_| format!("<{}> has an invalid '{}'", node.tag_name().name(), name))
+}
+
+fn optional_attribute<T: FromStr>(node: Node, name: &str, default: T) -> Result<T, String> {
+	match node.attribute(name) {
+		Some(_) => attribute(node, name),
+		None => Ok(default),
+	}
+}
+
+fn children<'a, 'input: 'a>(node: Node<'a, 'input>, name: &'static str) -> impl Iterator<Item = Node<'a, 'input>> {
+	node.children().filter(move |child| child.has_tag_name(name))
+}
+
+fn tmx_properties(node: Node) -> Result<Properties, String> {
+	let mut properties = Properties::new();
+	for group in children(node, "properties") {
+		for property in children(group, "property") {
+			let name: String = attribute(property, "name")?;
+			let value = property.attribute("value").or_else(|| property.text()).unwrap_or("");
+			let value = match property.attribute("type").unwrap_or("string") {
+				"bool" => PropertyValue::Bool(value == "true"),
+				"int" => PropertyValue::Int(value.parse().map_err(|_| format!("property '{}' is not an int", name))?),
+				"float" => PropertyValue::Float(value.parse().map_err(|_| format!("property '{}' is not a float", name))?),
+				_ => PropertyValue::String(value.to_owned()),
+			};
+			properties.insert(name, value);
+		}
+	}
+	Ok(properties)
+}
+
+fn tmx_tile_data(layer: Node) -> Result<Vec<u32>, String> {
+	let data = match children(layer, "data").next() {
+		Some(data) => data,
+		None => return Ok(vec![]),
+	};
+	match data.attribute("encoding") {
+		Some("csv") => data.text()
+			.unwrap_or("")
+			.split(',')
+			.map(|gid| gid.trim().parse().map_err(|_| format!("invalid tile '{}'", gid.trim())))
+			.collect(),
+		Some("base64") => {
+			if let Some(compression) = data.attribute("compression") {
+				return Err(format!("'{}' compressed tile layers are not supported, save them uncompressed", compression));
+			}
+			let text = data.text().unwrap_or("").split_whitespace().collect::<String>();
+			let bytes = base64::decode(&text).map_err(|error| format!("invalid base64 tile data: {}", error))?;
+			if bytes.len() % 4 != 0 {
+				return Err(format!("base64 tile data is {} bytes, which isn't a whole number of tiles", bytes.len()));
+			}
+			// Each tile is a little endian gid
+			Ok(bytes
+				.chunks(4)
+				.map(|gid| u32::from_le_bytes([gid[0], gid[1], gid[2], gid[3]]))
+				.collect())
+		},
+		None => children(data, "tile")
+			.map(|tile| optional_attribute(tile, "gid", 0))
+			.collect(),
+		Some(encoding) => Err(format!("'{}' tile layer encoding is not supported, use CSV, XML or base64", encoding)),
+	}
+}
+
+fn parse_tmx(text: &str) -> Result<Map, String> {
+	let document = roxmltree::Document::parse(text).map_err(|error| error.to_string())?;
+	let map = document.root_element();
+	let mut tilesets = Vec::<Tileset>::new();
+	let mut layers = Vec::<Layer>::new();
+	for node in map.children().filter(|node| node.is_element()) {
+		match node.tag_name().name() {
+			"tileset" => {
+				if let Some(source) = node.attribute("source") {
+					return Err(format!("external tileset '{}' is not supported, embed it in the map", source));
+				}
+				let mut tiles = HashMap::<u32, Properties>::new();
+				for tile in children(node, "tile") {
+					tiles.insert(attribute(tile, "id")?, tmx_properties(tile)?);
+				}
+				tilesets.push(Tileset {
+					first_gid: attribute(node, "firstgid")?,
+					tiles,
+				});
+			},
+			"layer" => layers.push(Layer::Tiles(TileLayer {
+				name: optional_attribute(node, "name", String::new())?,
+				data: tmx_tile_data(node)?,
+				properties: tmx_properties(node)?,
+				parallax: (optional_attribute(node, "parallaxx", 1.0)?, optional_attribute(node, "parallaxy", 1.0)?),
+			})),
+			"objectgroup" => {
+				let mut objects = Vec::<Object>::new();
+				for object in children(node, "object") {
+					objects.push(Object {
+						name: optional_attribute(object, "name", String::new())?,
+						kind: optional_attribute(object, "class", optional_attribute(object, "type", String::new())?)?,
+						x: attribute(object, "x")?,
+						y: attribute(object, "y")?,
+						width: optional_attribute(object, "width", 0.0)?,
+						height: optional_attribute(object, "height", 0.0)?,
+						gid: match object.attribute("gid") {
+							Some(_) => Some(attribute(object, "gid")?),
+							None => None,
+						},
+						properties: tmx_properties(object)?,
+					});
+				}
+				layers.push(Layer::Objects(objects));
+			},
+			_ => layers.push(Layer::Other),
+		}
+	}
+	Ok(Map {
+		width: attribute(map, "width")?,
+		height: attribute(map, "height")?,
+		tile_width: attribute(map, "tilewidth")?,
+		tile_height: attribute(map, "tileheight")?,
+		tilesets,
+		layers,
+	})
+}
+
+//////// Map -> LevelData ////////
+
+fn parse_tile(name: &str) -> Option<Tile> {
+	match name {
+		"Background"           => Some(Tile::Background),
+		"Ground"               => Some(Tile::Ground),
+		"Start"                => Some(Tile::Start),
+		"End"                  => Some(Tile::End),
+		"Ice"                  => Some(Tile::Ice),
+		"Mud"                  => Some(Tile::Mud),
+		"Bounce"               => Some(Tile::Bounce),
+		"Platform"             => Some(Tile::Platform),
+		"Spikes"               => Some(Tile::Spikes),
+		"Lava"                 => Some(Tile::Lava),
+		"Checkpoint"           => Some(Tile::Checkpoint),
+		"SlopeRight"           => Some(Tile::SlopeRight),
+		"SlopeLeft"            => Some(Tile::SlopeLeft),
+		"GentleSlopeRightLow"  => Some(Tile::GentleSlopeRightLow),
+		"GentleSlopeRightHigh" => Some(Tile::GentleSlopeRightHigh),
+		"GentleSlopeLeftLow"   => Some(Tile::GentleSlopeLeftLow),
+		"GentleSlopeLeftHigh"  => Some(Tile::GentleSlopeLeftHigh),
+		_ => None,
+	}
+}
+
+fn sprite_property(properties: &Properties) -> Option<usize> {
+	match properties.get(SPRITE_PROPERTY) {
+		Some(PropertyValue::Int(sprite)) if *sprite >= 0 => Some(*sprite as usize),
+		_ => None,
+	}
+}
+
+// Tiles default to ground using the sprite with the same index as the tile
+// in its tileset. Either can be overridden with the 'kind' and 'sprite'
+// custom properties.
+fn resolve_tile(tilesets: &[Tileset], gid: u32) -> Result<(Tile, Option<usize>, Option<&Properties>), String> {
+	let gid = gid & !FLIP_FLAGS;
+	if gid == 0 {
+		return Ok((Tile::Background, None, None));
+	}
+	let tileset = tilesets
+		.iter()
+		.filter(|tileset| tileset.first_gid <= gid)
+		.max_by_key(|tileset| tileset.first_gid)
+		.ok_or_else(|| format!("tile {} is not in any tileset", gid))?;
+	let id = gid - tileset.first_gid;
+	let properties = tileset.tiles.get(&id);
+	let tile = match properties.and_then(|properties| properties.get(KIND_PROPERTY)) {
+		Some(PropertyValue::String(kind)) => parse_tile(kind).ok_or_else(|| format!("tile {} has unknown kind '{}'", gid, kind))?,
+		Some(_) => return Err(format!("tile {} has a non-string kind", gid)),
+		None => Tile::Ground,
+	};
+	let sprite = properties
+		.and_then(sprite_property)
+		.unwrap_or(id as usize);
+	Ok((tile, Some(sprite), properties))
+}
+
+// Tile layers other than the gameplay layer are decoration. Unless they set
+// a 'depth' property, layers below the gameplay layer in Tiled are drawn
+// behind it and layers above it in front of the player. Their parallax
+// factor in Tiled becomes their scroll factor.
+fn build_layers(map: &Map, gameplay: usize) -> Result<Vec<LevelLayer>, String> {
+	let mut layers = Vec::<LevelLayer>::new();
+	for (i, layer) in map.layers.iter().enumerate() {
+		let TileLayer { name, data, properties, parallax } = match layer {
+			Layer::Tiles(layer) if i != gameplay => layer,
+			_ => continue,
+		};
+		if data.len() != map.width * map.height {
+			return Err(format!("tile layer '{}' has {} tiles, expected {}", name, data.len(), map.width * map.height));
+		}
+		let depth = match properties.get(DEPTH_PROPERTY) {
+			Some(PropertyValue::Float(depth)) => *depth as f32,
+			Some(PropertyValue::Int(depth)) => *depth as f32,
+			Some(_) => return Err(format!("tile layer '{}' has a non-numeric depth", name)),
+			None if i < gameplay => BACKGROUND_DEPTH - 0.01 * (gameplay - i) as f32,
+			None => FOREGROUND_DEPTH + 0.01 * (i - gameplay) as f32,
+		};
+		let mut sprites = Vec::<Option<usize>>::with_capacity(data.len());
+		for gid in data {
+			let (_, sprite, _) = resolve_tile(&map.tilesets, *gid)?;
+			sprites.push(sprite);
+		}
+		layers.push(LevelLayer {
+			name: name.clone(),
+			depth,
+			scroll: *parallax,
+			sprites,
+		});
+	}
+	Ok(layers)
+}
+
+fn build_level(map: Map) -> Result<LevelData, String> {
+	let mut tile_layers = map.layers
+		.iter()
+		.enumerate()
+		.filter_map(|(i, layer)| match layer {
+			Layer::Tiles(layer) => Some((i, &layer.name, &layer.data)),
+			_ => None,
+		});
+	let (gameplay, data) = match tile_layers.clone().find(|(_, name, _)| name.eq_ignore_ascii_case(GAMEPLAY_LAYER)) {
+		Some((i, _, data)) => (i, data),
+		None => match tile_layers.next() {
+			Some((i, _, data)) => (i, data),
+			None => return Err("the map has no tile layer".to_owned()),
+		},
+	};
+	if map.width == 0 || map.height == 0 {
+		return Err("the map is empty".to_owned());
+	}
+	let size = map.width.checked_mul(map.height).ok_or_else(|| format!("the map is too big at {}x{} tiles", map.width, map.height))?;
+	if data.len() != size {
+		return Err(format!("the tile layer has {} tiles, expected {}", data.len(), size));
+	}
+
+	let mut tiles = Vec::<Tile>::with_capacity(data.len());
+	let mut sprites = Vec::<Option<usize>>::with_capacity(data.len());
+	let mut properties = HashMap::<usize, Properties>::new();
+	for (i, gid) in data.iter().enumerate() {
+		let (tile, sprite, tile_properties) = resolve_tile(&map.tilesets, *gid)?;
+		tiles.push(tile);
+		sprites.push(sprite);
+		if let Some(tile_properties) = tile_properties {
+			if !tile_properties.is_empty() {
+				properties.insert(i, tile_properties.clone());
+			}
+		}
+	}
+	let mut level = LevelData::new(map.width, map.height, tiles, sprites);
+	level.properties = properties;
+	level.layers = build_layers(&map, gameplay)?;
+
+	for layer in map.layers {
+		let objects = match layer {
+			Layer::Objects(objects) => objects,
+			_ => continue,
+		};
+		for object in objects {
+			// Tile objects are positioned by their bottom left corner
+			let top = match object.gid {
+				Some(_) => object.y - object.height,
+				None => object.y,
+			};
+			let x = object.x / map.tile_width;
+			let y = top / map.tile_height;
+			let width = object.width / map.tile_width;
+			let height = object.height / map.tile_height;
+			let kind = if object.kind.is_empty() { &object.name } else { &object.kind };
+			if kind.eq_ignore_ascii_case(START_OBJECT) {
+				// The player stands on the bottom middle of the start object
+				level.starts.push((x + width / 2.0, y + height - 0.5));
+			} else if kind.eq_ignore_ascii_case(END_OBJECT) {
+				// The tiles under the end keep their kind, so it doesn't get
+				// in the player's way. A 'sprite' property only draws it
+				if let Some(sprite) = sprite_property(&object.properties) {
+					let left   = (x.floor().max(0.0) as usize).min(map.width  - 1);
+					let top    = (y.floor().max(0.0) as usize).min(map.height - 1);
+					let right  = ((x + width ).ceil() as usize).max(left + 1).min(map.width);
+					let bottom = ((y + height).ceil() as usize).max(top  + 1).min(map.height);
+					for tile_y in top..bottom {
+						for tile_x in left..right {
+							level.sprites[tile_y * map.width + tile_x] = Some(sprite);
+						}
+					}
+				}
+				level.ends.push(LevelArea { x, y, width, height });
+			} else {
+				level.objects.push(LevelObject {
+					name: object.name,
+					kind: object.kind,
+					x,
+					y,
+					width,
+					height,
+					properties: object.properties,
+				});
+			}
+		}
+	}
+	Ok(level)
+}
+
+fn from_bytes(bytes: &[u8], path: &Path, parse: fn(&str) -> Result<Map, String>) -> Result<LevelData, LevelLoadError> {
+	str::from_utf8(bytes)
+		.map_err(|error| error.to_string())
+		.and_then(parse)
+		.and_then(build_level)
+		.map_err(|error| LevelLoadError::Tiled(path.to_owned(), error))
+}
+
+fn load(path: &Path, parse: fn(&str) -> Result<Map, String>) -> Result<LevelData, LevelLoadError> {
+	let bytes = fs::read(path).map_err(|error| match error.kind() {
+		io::ErrorKind::NotFound => LevelLoadError::Missing(path.to_owned()),
+		_ => LevelLoadError::Io(path.to_owned(), error),
+	})?;
+	from_bytes(&bytes, path, parse)
+}
+
+pub fn tmx_from_bytes(bytes: &[u8], path: &Path) -> Result<LevelData, LevelLoadError> {
+	from_bytes(bytes, path, parse_tmx)
+}
+
+pub fn tmj_from_bytes(bytes: &[u8], path: &Path) -> Result<LevelData, LevelLoadError> {
+	from_bytes(bytes, path, parse_tmj)
+}
+
+pub fn load_tmx(path: &Path) -> Result<LevelData, LevelLoadError> {
+	load(path, parse_tmx)
+}
+
+pub fn load_tmj(path: &Path) -> Result<LevelData, LevelLoadError> {
+	load(path, parse_tmj)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::path::Path;
+
+	use crate::components::Tile;
+	use crate::levels::{LevelArea, LevelData, LevelLoadError, PropertyValue};
+	use super::{tmj_from_bytes, tmx_from_bytes};
+
+	const TILES: [Tile; 6] = [Tile::Background, Tile::Ice, Tile::Spikes, Tile::Ground, Tile::Ground, Tile::Ground];
+	const SPRITES: [Option<usize>; 6] = [None, Some(1), Some(7), Some(0), Some(0), Some(0)];
+
+	// A 3x2 map whose gameplay layer is `data`. The tileset's first tile is
+	// plain ground, the second is ice with a custom friction and the third is
+	// spikes with its own sprite.
+	fn tmx(data: &str) -> String {
+		format!(r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.9" orientation="orthogonal" width="3" height="2" tilewidth="16" tileheight="16">
+	<tileset firstgid="1" name="tiles" tilewidth="16" tileheight="16" tilecount="3" columns="3">
+		<tile id="1">
+			<properties>
+				<property name="kind" value="Ice"/>
+				<property name="friction" type="float" value="0.5"/>
+			</properties>
+		</tile>
+		<tile id="2">
+			<properties>
+				<property name="kind" value="Spikes"/>
+				<property name="sprite" type="int" value="7"/>
+			</properties>
+		</tile>
+	</tileset>
+	<layer id="1" name="gameplay" width="3" height="2">
+		{}
+	</layer>
+	<objectgroup id="2" name="objects">
+		<object id="1" name="start" x="8" y="16"/>
+		<object id="2" type="end" x="0" y="0" width="16" height="16"/>
+		<object id="3" name="bonus" type="coin" x="16" y="8">
+			<properties>
+				<property name="value" type="int" value="5"/>
+			</properties>
+		</object>
+	</objectgroup>
+</map>"#, data)
+	}
+
+	const CSV: &str = "<data encoding=\"csv\">\n0,2,3,\n1,1,1\n</data>";
+
+	const TMJ: &str = r#"{
+		"width": 3, "height": 2, "tilewidth": 16, "tileheight": 16,
+		"tilesets": [{
+			"firstgid": 1,
+			"tiles": [
+				{ "id": 1, "properties": [
+					{ "name": "kind", "type": "string", "value": "Ice" },
+					{ "name": "friction", "type": "float", "value": 0.5 }
+				] },
+				{ "id": 2, "properties": [
+					{ "name": "kind", "type": "string", "value": "Spikes" },
+					{ "name": "sprite", "type": "int", "value": 7 }
+				] }
+			]
+		}],
+		"layers": [
+			{ "type": "tilelayer", "name": "gameplay", "data": [0, 2, 3, 1, 1, 1] },
+			{ "type": "objectgroup", "objects": [
+				{ "name": "start", "x": 8, "y": 16 },
+				{ "type": "end", "x": 0, "y": 0, "width": 16, "height": 16 },
+				{ "name": "bonus", "type": "coin", "x": 16, "y": 8, "properties": [
+					{ "name": "value", "type": "int", "value": 5 }
+				] }
+			] }
+		]
+	}"#;
+
+	fn load_tmx(text: &str) -> Result<LevelData, LevelLoadError> {
+		tmx_from_bytes(text.as_bytes(), Path::new("test.tmx"))
+	}
+
+	fn assert_tiles(level: &LevelData) {
+		assert_eq!((level.width, level.height), (3, 2));
+		assert_eq!(level.tiles, TILES);
+		assert_eq!(level.sprites, SPRITES);
+	}
+
+	#[test]
+	fn csv_xml_and_base64_data_read_the_same() {
+		let xml = "<data><tile/><tile gid=\"2\"/><tile gid=\"3\"/><tile gid=\"1\"/><tile gid=\"1\"/><tile gid=\"1\"/></data>";
+		let base64 = "<data encoding=\"base64\">\n\tAAAAAAIAAAADAAAAAQAAAAEAAAABAAAA\n</data>";
+		for data in &[CSV, xml, base64] {
+			assert_tiles(&load_tmx(&tmx(data)).unwrap());
+		}
+	}
+
+	#[test]
+	fn tmj_reads_the_same_as_tmx() {
+		let tmj = tmj_from_bytes(TMJ.as_bytes(), Path::new("test.tmj")).unwrap();
+		let tmx = load_tmx(&tmx(CSV)).unwrap();
+		assert_tiles(&tmj);
+		assert_eq!(tmj.starts, tmx.starts);
+		assert_eq!(tmj.ends, tmx.ends);
+		assert_eq!(tmj.properties, tmx.properties);
+		assert_eq!(tmj.objects.len(), tmx.objects.len());
+	}
+
+	#[test]
+	fn flipped_tiles_are_the_same_tile() {
+		// Flipped horizontally, vertically and diagonally
+		let data = format!("<data encoding=\"csv\">0,{},{},{},1,1</data>", 2 | 0x8000_0000u32, 3 | 0x4000_0000u32, 1 | 0x2000_0000u32);
+		assert_tiles(&load_tmx(&tmx(&data)).unwrap());
+	}
+
+	#[test]
+	fn objects_become_starts_and_ends() {
+		let level = load_tmx(&tmx(CSV)).unwrap();
+		// The player stands at the bottom of a start point
+		assert_eq!(level.starts, vec![(0.5, 0.5)]);
+		assert_eq!(level.ends, vec![LevelArea { x: 0.0, y: 0.0, width: 1.0, height: 1.0 }]);
+		// The end doesn't turn the tile under it solid
+		assert_eq!(level.tiles[0], Tile::Background);
+		assert!(level.is_end(0, 0));
+		assert!(!level.is_end(1, 0));
+		assert_eq!(level.objects.len(), 1);
+		let coin = &level.objects[0];
+		assert_eq!((coin.name.as_str(), coin.kind.as_str()), ("bonus", "coin"));
+		assert_eq!((coin.x, coin.y), (1.0, 0.5));
+		assert_eq!(coin.properties.get("value"), Some(&PropertyValue::Int(5)));
+	}
+
+	#[test]
+	fn tile_properties_are_carried_through() {
+		let level = load_tmx(&tmx(CSV)).unwrap();
+		let ice = &level.properties[&1];
+		assert_eq!(ice.get("friction"), Some(&PropertyValue::Float(0.5)));
+		assert_eq!(ice.get("kind"), Some(&PropertyValue::String("Ice".to_owned())));
+		assert_eq!(level.properties[&2].get("sprite"), Some(&PropertyValue::Int(7)));
+		// Tiles without properties don't get an empty entry
+		assert!(!level.properties.contains_key(&0));
+		assert!(!level.properties.contains_key(&3));
+	}
+
+	#[test]
+	fn malformed_maps_are_errors() {
+		let maps = vec![
+			"not a map".to_owned(),
+			"<map width=\"3\" height=\"2\" tilewidth=\"16\"/>".to_owned(),
+			"<map width=\"3\" height=\"2\" tilewidth=\"16\" tileheight=\"16\"/>".to_owned(),
+			"<map width=\"-3\" height=\"2\" tilewidth=\"16\" tileheight=\"16\"><layer><data encoding=\"csv\">0</data></layer></map>".to_owned(),
+			"<map width=\"4294967296\" height=\"4294967296\" tilewidth=\"16\" tileheight=\"16\"><layer><data encoding=\"csv\">0</data></layer></map>".to_owned(),
+			tmx("<data encoding=\"csv\">0,2,three,1,1,1</data>"),
+			tmx("<data encoding=\"csv\">0,2,3</data>"),
+			tmx("<data encoding=\"csv\"></data>"),
+			tmx("<data><tile gid=\"two\"/></data>"),
+			tmx("<data encoding=\"base64\">!!!!</data>"),
+			tmx("<data encoding=\"base64\">AAA=</data>"),
+			tmx("<data encoding=\"base64\" compression=\"zlib\">eJxjYGBgAAAABAAB</data>"),
+			tmx("<data encoding=\"hex\">00</data>"),
+			tmx(CSV).replace("value=\"Ice\"", "value=\"Teleporter\""),
+			tmx(CSV).replace("type=\"float\" value=\"0.5\"", "type=\"float\" value=\"half\""),
+			tmx(CSV).replace("x=\"8\"", "x=\"left\""),
+			tmx(CSV).replace("<tileset firstgid=\"1\"", "<tileset firstgid=\"1\" source=\"tiles.tsx\""),
+			tmx(CSV).replace("<tileset firstgid=\"1\"", "<tileset firstgid=\"2\""),
+		];
+		for map in &maps {
+			match load_tmx(map) {
+				Err(LevelLoadError::Tiled(..)) => {},
+				result => panic!("expected an error for {:?}, got {:?}", map, result.map(|level| level.tiles)),
+			}
+		}
+		let maps = vec![
+			"{".to_owned(),
+			"{\"width\": 3}".to_owned(),
+			TMJ.replace("[0, 2, 3, 1, 1, 1]", "\"AAAAAAIAAAAD\""),
+			TMJ.replace("[0, 2, 3, 1, 1, 1]", "[0, 2, 3]"),
+			TMJ.replace("[0, 2, 3, 1, 1, 1]", "[0, 2, -3, 1, 1, 1]"),
+			TMJ.replace("\"firstgid\": 1,", "\"firstgid\": 1, \"source\": \"tiles.tsj\","),
+		];
+		for map in &maps {
+			match tmj_from_bytes(map.as_bytes(), Path::new("test.tmj")) {
+				Err(LevelLoadError::Tiled(..)) => {},
+				result => panic!("expected an error for {:?}, got {:?}", map, result.map(|level| level.tiles)),
+			}
+		}
+	}
+}
@@ -24,11 +24,9 @@
  *
  *******************************************************************************/
 
-mod components;
-mod states;
-mod systems;
-
 use amethyst::{
+	assets::{HotReloadBundle, Processor},
+	config::Config,
 	core::TransformBundle,
 	prelude::*,
 	input::{InputBundle},
@@ -37,11 +35,17 @@ use amethyst::{
 		types::DefaultBackend,
 		plugins::{RenderFlat2D, RenderToWindow},
 	},
+	tiles::{FlatEncoder, RenderTiles2D},
+	ui::{RenderUi, UiBundle},
 	utils::application_root_dir,
 	window::{DisplayConfig, MonitorIdent},
 	winit::EventsLoop,
 };
-use std::path::PathBuf;
+use std::{
+	path::PathBuf,
+	time::Duration,
+};
+use djam_4::{components, levels, states, systems};
 
 fn main() -> amethyst::Result<()> {
 	amethyst::start_logger(Default::default());
@@ -51,6 +55,7 @@ fn main() -> amethyst::Result<()> {
 	let resources_dir = app_root.join("resources");
 	let display_config_path = resources_dir.join("display_config.ron");
 	let binding_path = resources_dir.join("bindings.ron");
+	let manifest_path = resources_dir.join("levels").join("manifest.ron");
 
 	let events_loop = EventsLoop::new();
 	let monitor = MonitorIdent::from_primary(&events_loop);
@@ -60,12 +65,15 @@ fn main() -> amethyst::Result<()> {
 	display_config.dimensions = Some(monitor.monitor_id(&events_loop).get_dimensions().into());
 	display_config.fullscreen = Some(monitor);
 
+	let manifest = levels::LevelManifest::load(manifest_path)?;
+
 	let input_bundle = InputBundle::<systems::PlayerBindings>::new()
 		.with_bindings_from_file(binding_path)?;
 
-	let game_data = GameDataBuilder::default()
+	let mut game_data = GameDataBuilder::default()
 		.with_bundle(TransformBundle::new())?
 		.with_bundle(input_bundle)?
+		.with_bundle(UiBundle::<systems::PlayerBindings>::new())?
 		.with_bundle(
 			RenderingBundle::<DefaultBackend>::new()
 				.with_plugin(
@@ -73,13 +81,22 @@ fn main() -> amethyst::Result<()> {
 						.with_clear([0.0, 0.0, 0.0, 1.0]),
 				)
 				.with_plugin(RenderFlat2D::default())
+				.with_plugin(RenderTiles2D::<components::TileSprite, FlatEncoder>::default())
+				.with_plugin(RenderUi::default())
 		)?
-		.with(systems::ForceSystem, "force_system", &[])
-		.with(systems::PlayerMovementSystem, "player_movement_system", &["force_system", "input_system"])
-		.with(systems::CollisionSystem, "collision_system", &["player_movement_system"])
-		.with(systems::CameraFollowSystem, "player_post_collision_system", &["collision_system"]);
+		.with(Processor::<levels::LevelAsset>::new(), "level_processor", &[])
+		.with(Processor::<levels::Palette>::new(), "palette_processor", &[])
+		.with(Processor::<levels::AutoTileRules>::new(), "autotile_processor", &[])
+		.with(systems::InterpolationSystem, "interpolation_system", &[])
+		.with(systems::CameraFollowSystem, "player_post_collision_system", &["interpolation_system"])
+		.with(systems::ParallaxSystem, "parallax_system", &["player_post_collision_system"]);
+	if cfg!(debug_assertions) {
+		game_data = game_data.with_bundle(HotReloadBundle::default())?;
+	}
 
-	let mut game = Application::new(resources_dir, states::LevelState, game_data)?;
+	let mut game = Application::build(resources_dir, states::LoadingState::new(manifest))?
+		.with_fixed_step_length(Duration::from_secs_f32(systems::physics::PHYSICS_STEP))
+		.build(game_data)?;
 	game.run();
 
 	Ok(())
@@ -1,214 +1,725 @@
-/*******************************************************************************
- *
- * Copyright (c) 2020 Gnarwhal
- *
- * -----------------------------------------------------------------------------
- *
- * Permission is hereby granted, free of charge, to any person obtaining a copy
- * of this software and associated documentation files(the "Software"), to deal
- * in the Software without restriction, including without limitation the rights
- * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
- * copies of the Software, and to permit persons to whom the Software is
- * furnished to do so, subject to the following conditions
- *
- * The above copyright notice and this permission notice shall be included in all
- * copies or substantial portions of the Software.
- *
- * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
- * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
- * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
- * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
- * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
- * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
- * SOFTWARE.
- *
- *******************************************************************************/
-
-use image::Rgba;
-use amethyst::{
-	assets::{AssetStorage, Loader, Handle},
-	core::transform::Transform,
-	prelude::*,
-	ecs::Entity,
-	renderer::{Camera, ImageFormat, SpriteRender, SpriteSheet, SpriteSheetFormat, Texture}
-};
-use crate::components::Dynamic;
-use crate::components::Gravity;
-use crate::components::Player;
-use crate::components::Tile;
-
-pub const CAMERA_WIDTH:  f32 = 384.0;
-pub const CAMERA_HEIGHT: f32 = 216.0;
-
-pub const BLOCK_SIZE: f32 = 16.0;
-
-const BACKGROUND: Rgba<u8> = Rgba::<u8>([255, 255, 255, 255]);
-const GROUND:     Rgba<u8> = Rgba::<u8>([0,   0,   0,   255]);
-const START:      Rgba<u8> = Rgba::<u8>([0,   148, 255, 255]);
-const END:        Rgba<u8> = Rgba::<u8>([0,   216, 68,  255]);
-
-pub struct Level {
-	pub entities: Vec<Entity>,
-	pub width:  usize,
-	pub height: usize,
-	pub left:   f32,
-	pub bottom: f32,
-	pub right:  f32,
-	pub top:    f32,
-}
-
-impl Default for Level {
-	fn default() -> Self {
-		Level{
-			entities: vec![],
-			width:  0,
-			height: 0,
-			left:   0.0,
-			bottom: 0.0,
-			right:  0.0,
-			top:    0.0,
-		}
-	}
-}
-
-fn initialize_level(world: &mut World, sprite_sheet_handle: Handle<SpriteSheet>) {
-	let background_sprite = SpriteRender {
-		sprite_sheet: sprite_sheet_handle.clone(),
-		sprite_number: 1,
-	};
-	let ground_sprite = SpriteRender {
-		sprite_sheet: sprite_sheet_handle.clone(),
-		sprite_number: 2,
-	};
-	let start_sprite = SpriteRender {
-		sprite_sheet: sprite_sheet_handle.clone(),
-		sprite_number: 4,
-	};
-	let end_sprite = SpriteRender {
-		sprite_sheet: sprite_sheet_handle,
-		sprite_number: 3,
-	};
-
-	let level_image = image::open("resources/levels/level0.png").unwrap().into_rgba();
-	let width = level_image.width() as usize;
-	let height = level_image.height() as usize;
-	let center_x = BLOCK_SIZE * width  as f32 / 2.0;
-	let center_y = BLOCK_SIZE * height as f32 / 2.0;
-	let mut tile_map = Vec::<Entity>::with_capacity(level_image.width() as usize * level_image.height() as usize);
-	for (i, pixel) in level_image.pixels().enumerate() {
-		let mut transform = Transform::default();
-		transform.set_translation_xyz(BLOCK_SIZE * ((i % width) as f32 + 0.5) - center_x, BLOCK_SIZE * -((i / width) as f32 + 0.5) + center_y, -1.0);
-		match *pixel {
-			BACKGROUND => {
-				tile_map.push(world
-					.create_entity()
-					.with(Tile::Background)
-					.with(transform)
-					.build());
-			},
-			GROUND => {
-				tile_map.push(world
-					.create_entity()
-					.with(ground_sprite.clone())
-					.with(Tile::Ground)
-					.with(transform)
-					.build());
-			},
-			START => {
-				tile_map.push(world
-					.create_entity()
-					.with(start_sprite.clone())
-					.with(Tile::Start)
-					.with(transform)
-					.build());
-			},
-			END => {
-				tile_map.push(world
-					.create_entity()
-					.with(end_sprite.clone())
-					.with(Tile::End)
-					.with(transform)
-					.build());
-			},
-			_ => { panic!("Invalid level bitmap tile color!"); }
-		}
-	}
-	let level = Level {
-		entities: tile_map,
-		width,
-		height,
-		left:   -(BLOCK_SIZE * width  as f32 / 2.0),
-		bottom: -(BLOCK_SIZE * height as f32 / 2.0),
-		right:   (BLOCK_SIZE * width  as f32 / 2.0),
-		top:     (BLOCK_SIZE * height as f32 / 2.0),
-	};
-	world.insert(level);
-}
-
-fn initialize_camera(world: &mut World) {
-	let mut transform = Transform::default();
-	transform.set_translation_xyz(0.0, 0.0, 1.0);
-
-	world
-		.create_entity()
-		.with(Camera::standard_2d(CAMERA_WIDTH, CAMERA_HEIGHT))
-		.with(transform)
-		.build();
-}
-
-fn initialize_player(world: &mut World, sprite_sheet_handle: Handle<SpriteSheet>) {
-	let sprite_render = SpriteRender{
-		sprite_sheet: sprite_sheet_handle,
-		sprite_number: 0,
-	};
-
-	let mut transform = Transform::default();
-	transform.set_translation_xyz(BLOCK_SIZE * -11.0, BLOCK_SIZE * -12.5, 0.0);
-
-	world
-		.create_entity()
-		.with(sprite_render)
-		.with(Player::default())
-		.with(Dynamic::default())
-		.with(Gravity)
-		.with(transform)
-		.build();
-}
-
-fn load_sprite_sheet(world: &mut World) -> Handle<SpriteSheet> {
-	let texture_handle = {
-		let loader = world.read_resource::<Loader>();
-		let texture_storage = world.read_resource::<AssetStorage<Texture>>();
-		loader.load(
-			"sprites.png",
-			ImageFormat::default(),
-			(),
-			&texture_storage,
-		)
-	};
-
-	let loader = world.read_resource::<Loader>();
-	let sprite_sheet_store = world.read_resource::<AssetStorage<SpriteSheet>>();
-	loader.load(
-		"sprites.ron",
-		SpriteSheetFormat(texture_handle),
-		(),
-		&sprite_sheet_store,
-	)
-}
-
-pub struct LevelState;
-
-impl SimpleState for LevelState {
-	fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
-		let world = data.world;
-
-		let sprite_sheet_handle = load_sprite_sheet(world);
-
-		initialize_camera(world);
-		initialize_player(world, sprite_sheet_handle.clone());
-
-		world.register::<Tile>();
-		initialize_level(world, sprite_sheet_handle);
-	}
-}
\ No newline at end of file
+/*******************************************************************************
+ *
+ * Copyright (c) 2020 Gnarwhal
+ *
+ * -----------------------------------------------------------------------------
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files(the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ *
+ *******************************************************************************/
+
+use std::{
+	collections::HashMap,
+	path::{Path, PathBuf},
+};
+
+use amethyst::{
+	assets::{AssetStorage, Loader, Handle, ProgressCounter, RonFormat},
+	core::{
+		math::{Point3, Vector2, Vector3},
+		transform::Transform,
+		ArcThreadPool,
+		SystemDesc,
+	},
+	prelude::*,
+	ecs::{Dispatcher, DispatcherBuilder, Entity},
+	renderer::{Camera, SpriteRender, SpriteSheet},
+	shrev::{EventChannel, ReaderId},
+	tiles::{FlatEncoder, TileMap},
+};
+use log::{error, info};
+use crate::components::Collider;
+use crate::components::Dynamic;
+use crate::components::Gravity;
+use crate::components::Interpolation;
+use crate::components::Parallax;
+use crate::components::Player;
+use crate::components::Surface;
+use crate::components::Tile;
+use crate::components::TileSprite;
+use crate::levels::{AutoTileRules, LevelAsset, LevelData, LevelEntry, LevelFormat, LevelLoadError, LevelManifest, LevelObject, Palette, Properties, PropertyValue};
+use crate::states::level_error::LevelErrorState;
+use crate::states::results::ResultsState;
+use crate::systems::{self, LevelCompleteEvent};
+
+pub const CAMERA_WIDTH:  f32 = 384.0;
+pub const CAMERA_HEIGHT: f32 = 216.0;
+
+pub const BLOCK_SIZE: f32 = 16.0;
+
+const ACTIVE_SPRITE_PROPERTY: &str = "active_sprite";
+
+pub struct Level {
+	/// Tile map entities drawing the level, starting with the gameplay layer.
+	/// Collision uses `tiles` instead.
+	pub entities: Vec<Entity>,
+	/// Row major, starting from the top left, the same as the level file.
+	pub tiles: Vec<Tile>,
+	pub starts: Vec<Vector2<f32>>,
+	/// The bottom left and top right corners of each end area.
+	pub ends: Vec<(Vector2<f32>, Vector2<f32>)>,
+	pub objects: Vec<LevelObject>,
+	pub properties: HashMap<usize, Properties>,
+	/// The sprite each tile and sprite switches to once activated, from the
+	/// palette.
+	pub active_sprites: HashMap<(Tile, Option<usize>), usize>,
+	pub width:  usize,
+	pub height: usize,
+	pub left:   f32,
+	pub bottom: f32,
+	pub right:  f32,
+	pub top:    f32,
+	pub complete: bool,
+}
+
+impl Default for Level {
+	fn default() -> Self {
+		Level{
+			entities: vec![],
+			tiles: vec![],
+			starts: vec![],
+			ends: vec![],
+			objects: vec![],
+			properties: HashMap::new(),
+			active_sprites: HashMap::new(),
+			width:  0,
+			height: 0,
+			left:   0.0,
+			bottom: 0.0,
+			right:  0.0,
+			top:    0.0,
+			complete: false,
+		}
+	}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Checkpoint {
+	/// Column and row counted from the bottom left, like `Level::tile_at`.
+	pub x: usize,
+	pub y: usize,
+	/// The sprite the checkpoint had before it was activated.
+	pub sprite: Option<usize>,
+}
+
+/// Where the player comes back after dying in the current level, which is
+/// the start until they touch a checkpoint.
+#[derive(Clone, Copy, Debug)]
+pub struct Respawn {
+	pub position: Vector2<f32>,
+	pub checkpoint: Option<Checkpoint>,
+}
+
+impl Respawn {
+	pub fn at_start(start: Vector2<f32>) -> Self {
+		Respawn {
+			position: start,
+			checkpoint: None,
+		}
+	}
+}
+
+impl Default for Respawn {
+	fn default() -> Self {
+		Respawn::at_start(Vector2::new(0.0, 0.0))
+	}
+}
+
+/// How the current attempt at the level is going.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LevelStats {
+	pub time: f32,
+	pub deaths: usize,
+	pub jumps: usize,
+}
+
+impl Level {
+	/// Builds the level's collision and layout from its data without creating
+	/// any entities, so it can also be used outside of the game.
+	pub fn new(level_data: &LevelData) -> Self {
+		let width = level_data.width;
+		let height = level_data.height;
+		Level {
+			entities: vec![],
+			tiles: level_data.tiles.clone(),
+			starts: find_starts(level_data),
+			ends: find_ends(level_data),
+			objects: level_data.objects.clone(),
+			properties: level_data.properties.clone(),
+			active_sprites: HashMap::new(),
+			width,
+			height,
+			left:   -(BLOCK_SIZE * width  as f32 / 2.0),
+			bottom: -(BLOCK_SIZE * height as f32 / 2.0),
+			right:   (BLOCK_SIZE * width  as f32 / 2.0),
+			top:     (BLOCK_SIZE * height as f32 / 2.0),
+			complete: false,
+		}
+	}
+
+	/// Looks up a tile by its column and row counted from the bottom left,
+	/// which is how the level is laid out in the world.
+	pub fn tile_at(&self, x: usize, y: usize) -> Tile {
+		self.tiles[(self.height - y - 1) * self.width + x]
+	}
+
+	/// The surface of a tile counted from the bottom left. Tiles can override
+	/// their kind's surface with 'friction', 'bounce' and 'speed' properties.
+	pub fn surface_at(&self, x: usize, y: usize) -> Surface {
+		let index = (self.height - y - 1) * self.width + x;
+		let mut surface = self.tiles[index].surface();
+		if let Some(properties) = self.properties.get(&index) {
+			let number = |name: &str| match properties.get(name) {
+				Some(PropertyValue::Float(value)) => Some(*value as f32),
+				Some(PropertyValue::Int(value)) => Some(*value as f32),
+				_ => None,
+			};
+			surface.friction = number("friction").unwrap_or(surface.friction);
+			surface.bounce = number("bounce").unwrap_or(surface.bounce);
+			surface.speed = number("speed").unwrap_or(surface.speed);
+		}
+		surface
+	}
+
+	/// The sprite a tile counted from the bottom left switches to once it's
+	/// activated. An 'active_sprite' tile property overrides the palette.
+	pub fn active_sprite(&self, x: usize, y: usize, sprite: Option<usize>) -> Option<usize> {
+		let index = (self.height - y - 1) * self.width + x;
+		let property = self.properties
+			.get(&index)
+			.and_then(|properties| match properties.get(ACTIVE_SPRITE_PROPERTY) {
+				Some(PropertyValue::Int(active)) if *active >= 0 => Some(*active as usize),
+				_ => None,
+			});
+		property.or_else(|| self.active_sprites.get(&(self.tiles[index], sprite)).copied())
+	}
+
+	/// Whether a body with this collider at this position would be inside a
+	/// solid tile or outside of the level.
+	pub fn overlaps_solid(&self, position: &Vector3<f32>, collider: &Collider) -> bool {
+		const SHRINK: f32 = 0.01;
+		let center = collider.center(position);
+		let half_x = (collider.half_extents.x - SHRINK).max(0.0);
+		let half_y = (collider.half_extents.y - SHRINK).max(0.0);
+		let x = center.x - self.left;
+		let y = center.y - self.bottom;
+		if x - half_x < 0.0 || y - half_y < 0.0 || x + half_x > self.right - self.left || y + half_y > self.top - self.bottom {
+			return true;
+		}
+		let left   = ((x - half_x) / BLOCK_SIZE).floor() as usize;
+		let bottom = ((y - half_y) / BLOCK_SIZE).floor() as usize;
+		let right  = (((x + half_x) / BLOCK_SIZE).floor() as usize).min(self.width  - 1);
+		let top    = (((y + half_y) / BLOCK_SIZE).floor() as usize).min(self.height - 1);
+		(left..=right).any(|i| (bottom..=top).any(|j| self.tile_at(i, j).is_solid()))
+	}
+}
+
+fn find_starts(level_data: &LevelData) -> Vec<Vector2<f32>> {
+	let center_x = BLOCK_SIZE * level_data.width  as f32 / 2.0;
+	let center_y = BLOCK_SIZE * level_data.height as f32 / 2.0;
+	level_data
+		.start_points()
+		.into_iter()
+		.map(|(x, y)| Vector2::new(BLOCK_SIZE * x - center_x, center_y - BLOCK_SIZE * y))
+		.collect()
+}
+
+fn find_ends(level_data: &LevelData) -> Vec<(Vector2<f32>, Vector2<f32>)> {
+	let center_x = BLOCK_SIZE * level_data.width  as f32 / 2.0;
+	let center_y = BLOCK_SIZE * level_data.height as f32 / 2.0;
+	level_data.ends
+		.iter()
+		.map(|end| (
+			Vector2::new(BLOCK_SIZE * end.x - center_x, center_y - BLOCK_SIZE * (end.y + end.height)),
+			Vector2::new(BLOCK_SIZE * (end.x + end.width) - center_x, center_y - BLOCK_SIZE * end.y),
+		))
+		.collect()
+}
+
+// Tile map coordinates start from the top left just like the level file, so
+// each sprite goes in the tile with the same column and row.
+pub fn build_tile_map(sprites: &[Option<usize>], width: usize, height: usize, sprite_sheet_handle: Option<Handle<SpriteSheet>>) -> TileMap<TileSprite, FlatEncoder> {
+	let mut tile_map = TileMap::<TileSprite, FlatEncoder>::new(
+		Vector3::new(width as u32, height as u32, 1),
+		Vector3::new(BLOCK_SIZE as u32, BLOCK_SIZE as u32, 1),
+		sprite_sheet_handle,
+	);
+	for (i, sprite) in sprites.iter().enumerate() {
+		if let Some(tile) = tile_map.get_mut(&Point3::new((i % width) as u32, (i / width) as u32, 0)) {
+			tile.0 = *sprite;
+		}
+	}
+	tile_map
+}
+
+fn initialize_tile_map(world: &mut World, sprite_sheet_handle: Handle<SpriteSheet>, level_data: &LevelData, sprites: &[Option<usize>], depth: f32, scroll: (f32, f32)) -> Entity {
+	let mut transform = Transform::default();
+	transform.set_translation_xyz(0.0, 0.0, depth);
+	let mut builder = world
+		.create_entity()
+		.with(build_tile_map(sprites, level_data.width, level_data.height, Some(sprite_sheet_handle)))
+		.with(transform);
+	if scroll != (1.0, 1.0) {
+		builder = builder.with(Parallax { scroll: Vector2::new(scroll.0, scroll.1) });
+	}
+	builder.build()
+}
+
+// One tile map for the gameplay layer and one for each decoration layer.
+fn initialize_tile_maps(world: &mut World, sprite_sheet_handle: Handle<SpriteSheet>, level_data: &LevelData) -> Vec<Entity> {
+	let mut entities = vec![initialize_tile_map(world, sprite_sheet_handle.clone(), level_data, &level_data.sprites, -1.0, (1.0, 1.0))];
+	for layer in &level_data.layers {
+		entities.push(initialize_tile_map(world, sprite_sheet_handle.clone(), level_data, &layer.sprites, layer.depth, layer.scroll));
+	}
+	entities
+}
+
+fn initialize_camera(world: &mut World) {
+	let mut transform = Transform::default();
+	transform.set_translation_xyz(0.0, 0.0, 1.0);
+
+	world
+		.create_entity()
+		.with(Camera::standard_2d(CAMERA_WIDTH, CAMERA_HEIGHT))
+		.with(transform)
+		.build();
+}
+
+fn initialize_player(world: &mut World, sprite_sheet_handle: Handle<SpriteSheet>, start: Vector2<f32>) -> Entity {
+	let sprite_render = SpriteRender{
+		sprite_sheet: sprite_sheet_handle,
+		sprite_number: 0,
+	};
+
+	let mut transform = Transform::default();
+	transform.set_translation_xyz(start.x, start.y, 0.0);
+
+	world
+		.create_entity()
+		.with(sprite_render)
+		.with(Player::default())
+		.with(Dynamic::default())
+		.with(Gravity)
+		.with(Interpolation::new(Vector3::new(start.x, start.y, 0.0)))
+		.with(transform)
+		.build()
+}
+
+// Physics runs in fixed steps so that it plays out the same at any frame
+// rate. The player is drawn between steps by the interpolation systems.
+fn create_physics_dispatcher(world: &mut World) -> Dispatcher<'static, 'static> {
+	let pool = (*world.read_resource::<ArcThreadPool>()).clone();
+	let level_stats_system = systems::LevelStatsSystemDesc::default().build(world);
+	let mut dispatcher = DispatcherBuilder::new()
+		.with_pool(pool)
+		.with(systems::InterpolationStartSystem, "interpolation_start_system", &[])
+		.with(systems::ForceSystem, "force_system", &["interpolation_start_system"])
+		.with(systems::PlayerMovementSystem, "player_movement_system", &["force_system"])
+		.with(systems::CollisionSystem, "collision_system", &["player_movement_system"])
+		.with(systems::CheckpointSystem, "checkpoint_system", &["collision_system"])
+		.with(systems::HazardSystem, "hazard_system", &["checkpoint_system"])
+		.with(systems::LevelExitSystem, "level_exit_system", &["hazard_system"])
+		.with(level_stats_system, "level_stats_system", &["hazard_system"])
+		.with(systems::InterpolationEndSystem, "interpolation_end_system", &["level_exit_system", "level_stats_system"])
+		.build();
+	dispatcher.setup(world);
+	dispatcher
+}
+
+/// Handles to a level, its decoration layers and its palette, which load
+/// together.
+pub struct LevelAssets {
+	pub level: Handle<LevelAsset>,
+	pub layers: Vec<Handle<LevelAsset>>,
+	pub palette: Handle<Palette>,
+}
+
+impl LevelAssets {
+	// Paths in the manifest are relative to the resources directory.
+	pub fn load(world: &World, entry: &LevelEntry, progress: &mut ProgressCounter) -> Self {
+		let loader = world.read_resource::<Loader>();
+		LevelAssets {
+			level: loader.load(
+				entry.file.as_str(),
+				LevelFormat::new(Path::new(&entry.file)),
+				&mut *progress,
+				&world.read_resource::<AssetStorage<LevelAsset>>(),
+			),
+			layers: entry.layers
+				.iter()
+				.map(|layer| loader.load(
+					layer.file.as_str(),
+					LevelFormat::new(Path::new(&layer.file)),
+					&mut *progress,
+					&world.read_resource::<AssetStorage<LevelAsset>>(),
+				))
+				.collect(),
+			palette: loader.load(
+				entry.palette(),
+				RonFormat,
+				&mut *progress,
+				&world.read_resource::<AssetStorage<Palette>>(),
+			),
+		}
+	}
+}
+
+pub struct LevelState {
+	manifest: LevelManifest,
+	current: usize,
+	/// Why the current level failed to load, until it's shown to the player.
+	error: Option<LevelLoadError>,
+	sprite_sheet_handle: Handle<SpriteSheet>,
+	autotile_handle: Handle<AutoTileRules>,
+	player: Option<Entity>,
+	assets: Option<LevelAssets>,
+	progress: Option<ProgressCounter>,
+	/// The level and layer asset versions, palette and auto-tiling rules the
+	/// current level was built from, to notice when any of them are hot
+	/// reloaded.
+	level_versions: Vec<usize>,
+	palette: Option<Palette>,
+	autotile: Option<AutoTileRules>,
+	showing_results: bool,
+	showing_error: bool,
+	physics: Option<Dispatcher<'static, 'static>>,
+	completion_reader: Option<ReaderId<LevelCompleteEvent>>,
+}
+
+impl LevelState {
+	/// Starts on the first level in the manifest, whose assets were loaded
+	/// with `progress`.
+	pub fn new(manifest: LevelManifest, sprite_sheet_handle: Handle<SpriteSheet>, autotile_handle: Handle<AutoTileRules>, assets: LevelAssets, progress: ProgressCounter) -> Self {
+		LevelState {
+			manifest,
+			current: 0,
+			error: None,
+			sprite_sheet_handle,
+			autotile_handle,
+			player: None,
+			assets: Some(assets),
+			progress: Some(progress),
+			level_versions: vec![],
+			palette: None,
+			autotile: None,
+			showing_results: false,
+			showing_error: false,
+			physics: None,
+			completion_reader: None,
+		}
+	}
+
+	// Starts loading the current level and its palette in the background.
+	fn begin_loading(&mut self, world: &mut World) {
+		let mut progress = ProgressCounter::new();
+		self.assets = Some(LevelAssets::load(world, self.manifest.get(self.current).unwrap(), &mut progress));
+		self.progress = Some(progress);
+	}
+
+	fn read_level(&mut self, world: &World) -> Result<(LevelData, Level, Vector2<f32>), LevelLoadError> {
+		let entry = self.manifest.get(self.current).unwrap();
+		let level_assets = world.read_resource::<AssetStorage<LevelAsset>>();
+		let palettes = world.read_resource::<AssetStorage<Palette>>();
+		let level_asset = self.assets
+			.as_ref()
+			.and_then(|assets| level_assets.get(&assets.level))
+			.ok_or_else(|| LevelLoadError::Missing(PathBuf::from(&entry.file)))?;
+		let mut layer_assets = Vec::<&LevelAsset>::with_capacity(entry.layers.len());
+		for (i, layer) in entry.layers.iter().enumerate() {
+			layer_assets.push(self.assets
+				.as_ref()
+				.and_then(|assets| assets.layers.get(i))
+				.and_then(|handle| level_assets.get(handle))
+				.ok_or_else(|| LevelLoadError::Missing(PathBuf::from(&layer.file)))?);
+		}
+		let palette = self.assets
+			.as_ref()
+			.and_then(|assets| palettes.get(&assets.palette))
+			.ok_or_else(|| LevelLoadError::Missing(PathBuf::from(entry.palette())))?;
+		let autotile = world
+			.read_resource::<AssetStorage<AutoTileRules>>()
+			.get(&self.autotile_handle)
+			.cloned()
+			.unwrap_or_default();
+		self.level_versions = Some(level_asset)
+			.into_iter()
+			.chain(layer_assets.iter().copied())
+			.map(|asset| asset.version)
+			.collect();
+		self.palette = Some(palette.clone());
+
+		let mut level_data = level_asset.to_level(palette)?;
+		level_data.sprites = autotile.apply(&level_data);
+		self.autotile = Some(autotile);
+		for (layer, layer_asset) in entry.layers.iter().zip(layer_assets) {
+			let layer = layer_asset.to_layer(palette, layer, level_data.width, level_data.height)?;
+			level_data.layers.push(layer);
+		}
+		let mut level = Level::new(&level_data);
+		level.active_sprites = palette.active_sprites();
+		let start_index = entry.start.unwrap_or(0);
+		match level.starts.get(start_index) {
+			Some(start) => {
+				let start = *start;
+				Ok((level_data, level, start))
+			},
+			None => Err(LevelLoadError::MissingStart(PathBuf::from(&entry.file), start_index)),
+		}
+	}
+
+	fn assets_changed(&self, world: &World) -> bool {
+		let level_assets = world.read_resource::<AssetStorage<LevelAsset>>();
+		let palettes = world.read_resource::<AssetStorage<Palette>>();
+		let level_changed = self.assets
+			.as_ref()
+			.map_or(false, |assets| Some(&assets.level)
+				.into_iter()
+				.chain(&assets.layers)
+				.zip(&self.level_versions)
+				.any(|(handle, version)| level_assets.get(handle).map_or(false, |asset| asset.version != *version)));
+		let palette_changed = self.assets
+			.as_ref()
+			.and_then(|assets| palettes.get(&assets.palette))
+			.map_or(false, |palette| Some(palette) != self.palette.as_ref());
+		let autotile_changed = world
+			.read_resource::<AssetStorage<AutoTileRules>>()
+			.get(&self.autotile_handle)
+			.map_or(false, |autotile| Some(autotile) != self.autotile.as_ref());
+		level_changed || palette_changed || autotile_changed
+	}
+
+	fn unload_level(&mut self, world: &mut World) {
+		let level = std::mem::take(&mut *world.write_resource::<Level>());
+		world.delete_entities(&level.entities).unwrap();
+		if let Some(player) = self.player.take() {
+			world.delete_entity(player).unwrap();
+		}
+		self.assets = None;
+	}
+
+	fn load_level(&mut self, world: &mut World) -> Result<(), LevelLoadError> {
+		let sprite_sheet_handle = self.sprite_sheet_handle.clone();
+		let (level_data, mut level, start) = self.read_level(world)?;
+
+		level.entities = initialize_tile_maps(world, sprite_sheet_handle.clone(), &level_data);
+		world.insert(level);
+		world.insert(Respawn::at_start(start));
+		world.insert(LevelStats::default());
+		self.player = Some(initialize_player(world, sprite_sheet_handle, start));
+		Ok(())
+	}
+
+	// Rebuilds the level in place after its assets are hot reloaded. The
+	// player stays where they are unless the new level put them inside the
+	// ground.
+	fn reload_level(&mut self, world: &mut World) {
+		let name = self.manifest.get(self.current).unwrap().name.clone();
+		let (level_data, mut level, start) = match self.read_level(world) {
+			Ok(level) => level,
+			Err(error) => {
+				error!("Failed to reload level '{}': {}", name, error);
+				return;
+			},
+		};
+
+		let old_level = std::mem::take(&mut *world.write_resource::<Level>());
+		world.delete_entities(&old_level.entities).unwrap();
+		level.entities = initialize_tile_maps(world, self.sprite_sheet_handle.clone(), &level_data);
+
+		if let Some(player) = self.player {
+			let mut transforms = world.write_storage::<Transform>();
+			let mut dynamics = world.write_storage::<Dynamic>();
+			let collider = world.read_storage::<Collider>().get(player).copied().unwrap_or_default();
+			if let Some(transform) = transforms.get_mut(player) {
+				if level.overlaps_solid(transform.translation(), &collider) {
+					transform.set_translation_xyz(start.x, start.y, 0.0);
+					if let Some(dynamic) = dynamics.get_mut(player) {
+						*dynamic = Dynamic::default();
+					}
+				}
+			}
+		}
+
+		world.insert(level);
+		world.insert(Respawn::at_start(start));
+		info!("Reloaded level '{}'", name);
+	}
+
+	// Builds the current level once its assets are done loading. Only the
+	// first of the assets that failed to load gets reported.
+	fn finish_loading(&mut self, world: &mut World, progress: ProgressCounter) -> Result<(), LevelLoadError> {
+		let name = self.manifest.get(self.current).unwrap().name.clone();
+		let mut errors = progress
+			.errors()
+			.into_iter()
+			.map(|failure| LevelLoadError::Asset(PathBuf::from(&failure.asset_name), failure.error.to_string()))
+			.collect::<Vec<_>>();
+		for error in &errors {
+			error!("Failed to load level '{}': {}", name, error);
+		}
+		if !errors.is_empty() {
+			return Err(errors.remove(0));
+		}
+		self.load_level(world).map_err(|error| {
+			error!("Failed to load level '{}': {}", name, error);
+			error
+		})
+	}
+
+	// Moves on to loading the next level, or quits after the last one.
+	fn next_level(&mut self, world: &mut World) -> SimpleTrans {
+		self.unload_level(world);
+		self.current += 1;
+		if self.current < self.manifest.len() {
+			self.begin_loading(world);
+			Trans::None
+		} else {
+			Trans::Quit
+		}
+	}
+}
+
+impl SimpleState for LevelState {
+	fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+		let world = data.world;
+
+		initialize_camera(world);
+		self.physics = Some(create_physics_dispatcher(world));
+		self.completion_reader = Some(world.write_resource::<EventChannel<LevelCompleteEvent>>().register_reader());
+	}
+
+	fn fixed_update(&mut self, data: StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+		if let Some(physics) = self.physics.as_mut() {
+			physics.dispatch(data.world);
+		}
+		Trans::None
+	}
+
+	fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+		let world = &mut *data.world;
+
+		if let Some(error) = self.error.take() {
+			self.showing_error = true;
+			let name = self.manifest.get(self.current).unwrap().name.clone();
+			return Trans::Push(Box::new(LevelErrorState::new(name, error)));
+		}
+		if self.showing_error {
+			// Back from the error, so skip the broken level instead of
+			// trying it again
+			self.showing_error = false;
+			return self.next_level(world);
+		}
+		if self.showing_results {
+			// Back from the results, so move on to the next level
+			self.showing_results = false;
+			return self.next_level(world);
+		}
+		if let Some(progress) = self.progress.take() {
+			if progress.is_complete() {
+				self.error = self.finish_loading(world, progress).err();
+			} else {
+				self.progress = Some(progress);
+			}
+			return Trans::None;
+		}
+		if self.assets_changed(world) {
+			self.reload_level(world);
+		}
+		let completion = self.completion_reader
+			.as_mut()
+			.and_then(|reader| world.read_resource::<EventChannel<LevelCompleteEvent>>().read(reader).last().copied());
+		match completion {
+			Some(completion) => {
+				self.showing_results = true;
+				let name = self.manifest.get(self.current).unwrap().name.clone();
+				Trans::Push(Box::new(ResultsState::new(name, completion.stats)))
+			},
+			None => Trans::None,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::path::Path;
+
+	use amethyst::core::math::Point3;
+	use image::{Rgba, RgbaImage};
+	use crate::components::Tile;
+	use crate::levels::{bitmap, Palette, PaletteEntry};
+	use super::{build_tile_map, Level};
+
+	const BACKGROUND: [u8; 4] = [255, 255, 255, 255];
+	const GROUND:     [u8; 4] = [0, 0, 0, 255];
+	const START:      [u8; 4] = [0, 148, 255, 255];
+	const END:        [u8; 4] = [0, 216, 68, 255];
+
+	fn palette() -> Palette {
+		Palette {
+			entries: vec![
+				PaletteEntry { color: BACKGROUND, tile: Tile::Background, sprite: None, active_sprite: None },
+				PaletteEntry { color: GROUND, tile: Tile::Ground, sprite: Some(2), active_sprite: None },
+				PaletteEntry { color: START, tile: Tile::Start, sprite: Some(4), active_sprite: None },
+				PaletteEntry { color: END, tile: Tile::End, sprite: Some(3), active_sprite: None },
+			],
+		}
+	}
+
+	// Every row is different so that a flipped level doesn't match.
+	fn image() -> RgbaImage {
+		let rows = [
+			[BACKGROUND, BACKGROUND, END],
+			[START, BACKGROUND, GROUND],
+			[GROUND, GROUND, BACKGROUND],
+			[GROUND, GROUND, GROUND],
+		];
+		let mut image = RgbaImage::new(3, 4);
+		for (y, row) in rows.iter().enumerate() {
+			for (x, color) in row.iter().enumerate() {
+				image.put_pixel(x as u32, y as u32, Rgba(*color));
+			}
+		}
+		image
+	}
+
+	#[test]
+	fn tile_map_matches_bitmap() {
+		let palette = palette();
+		let image = image();
+		let level_data = bitmap::from_image(&image, &palette, Path::new("test.png")).unwrap();
+		let tile_map = build_tile_map(&level_data.sprites, level_data.width, level_data.height, None);
+		for (x, y, pixel) in image.enumerate_pixels() {
+			let entry = palette.lookup(pixel.0).unwrap();
+			let tile = tile_map.get(&Point3::new(x, y, 0)).unwrap();
+			assert_eq!(tile.0, entry.sprite, "sprite at ({}, {})", x, y);
+		}
+	}
+
+	#[test]
+	fn level_counts_rows_from_bottom() {
+		let palette = palette();
+		let image = image();
+		let level_data = bitmap::from_image(&image, &palette, Path::new("test.png")).unwrap();
+		let level = Level::new(&level_data);
+		for (x, y, pixel) in image.enumerate_pixels() {
+			let entry = palette.lookup(pixel.0).unwrap();
+			assert_eq!(level.tile_at(x as usize, (image.height() - y - 1) as usize), entry.tile, "tile at ({}, {})", x, y);
+		}
+	}
+}
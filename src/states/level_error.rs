@@ -0,0 +1,110 @@
+/*******************************************************************************
+ *
+ * Copyright (c) 2020 Gnarwhal
+ *
+ * -----------------------------------------------------------------------------
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files(the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ *
+ *******************************************************************************/
+
+use amethyst::{
+	assets::{AssetStorage, Loader},
+	ecs::Entity,
+	prelude::*,
+	ui::{get_default_font, Anchor, FontAsset, LineMode, UiText, UiTransform},
+};
+use crate::levels::LevelLoadError;
+use crate::states::results::jump_is_down;
+
+const TEXT_SIZE: f32 = 24.0;
+
+/// Shows why a level failed to load until the player jumps to skip it.
+pub struct LevelErrorState {
+	name: String,
+	error: LevelLoadError,
+	text: Option<Entity>,
+	released: bool,
+}
+
+impl LevelErrorState {
+	pub fn new(name: String, error: LevelLoadError) -> Self {
+		LevelErrorState {
+			name,
+			error,
+			text: None,
+			released: false,
+		}
+	}
+}
+
+impl SimpleState for LevelErrorState {
+	fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+		let world = data.world;
+
+		let font = get_default_font(
+			&world.read_resource::<Loader>(),
+			&world.read_resource::<AssetStorage<FontAsset>>(),
+		);
+		let text = format!(
+			"{} failed to load!\n{}\n\nJump to skip it",
+			self.name,
+			self.error,
+		);
+		self.text = Some(world
+			.create_entity()
+			.with(UiTransform::new(
+				"level_error".to_string(),
+				Anchor::Middle,
+				Anchor::Middle,
+				0.0,
+				0.0,
+				1.0,
+				800.0,
+				TEXT_SIZE * 12.0,
+			))
+			.with(UiText::new(
+				font,
+				text,
+				[1.0, 0.4, 0.4, 1.0],
+				TEXT_SIZE,
+				LineMode::Wrap,
+				Anchor::Middle,
+			))
+			.build());
+	}
+
+	fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+		if let Some(text) = self.text.take() {
+			data.world.delete_entity(text).unwrap();
+		}
+	}
+
+	fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+		let jumping = jump_is_down(data.world);
+		if !self.released {
+			self.released = !jumping;
+			Trans::None
+		} else if jumping {
+			Trans::Pop
+		} else {
+			Trans::None
+		}
+	}
+}
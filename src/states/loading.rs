@@ -0,0 +1,138 @@
+/*******************************************************************************
+ *
+ * Copyright (c) 2020 Gnarwhal
+ *
+ * -----------------------------------------------------------------------------
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files(the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ *
+ *******************************************************************************/
+
+use amethyst::{
+	assets::{AssetStorage, Handle, Loader, ProgressCounter, RonFormat},
+	prelude::*,
+	renderer::{ImageFormat, SpriteSheet, SpriteSheetFormat, Texture},
+};
+use log::{error, info};
+use crate::levels::{AutoTileRules, LevelManifest};
+use crate::states::level::{LevelAssets, LevelState};
+
+fn load_sprite_sheet(world: &mut World, progress: &mut ProgressCounter) -> Handle<SpriteSheet> {
+	let texture_handle = {
+		let loader = world.read_resource::<Loader>();
+		let texture_storage = world.read_resource::<AssetStorage<Texture>>();
+		loader.load(
+			"sprites.png",
+			ImageFormat::default(),
+			&mut *progress,
+			&texture_storage,
+		)
+	};
+
+	let loader = world.read_resource::<Loader>();
+	let sprite_sheet_store = world.read_resource::<AssetStorage<SpriteSheet>>();
+	loader.load(
+		"sprites.ron",
+		SpriteSheetFormat(texture_handle),
+		&mut *progress,
+		&sprite_sheet_store,
+	)
+}
+
+fn load_autotile_rules(world: &mut World, progress: &mut ProgressCounter) -> Handle<AutoTileRules> {
+	let loader = world.read_resource::<Loader>();
+	loader.load(
+		"levels/autotile.ron",
+		RonFormat,
+		progress,
+		&world.read_resource::<AssetStorage<AutoTileRules>>(),
+	)
+}
+
+/// Loads the sprite sheet, auto-tiling rules and the first level before handing them over to
+/// `LevelState`. The first level is tracked separately, so that `LevelState` can report it failing
+/// like any other level.
+pub struct LoadingState {
+	manifest: Option<LevelManifest>,
+	progress: ProgressCounter,
+	level_progress: Option<ProgressCounter>,
+	sprite_sheet_handle: Option<Handle<SpriteSheet>>,
+	autotile_handle: Option<Handle<AutoTileRules>>,
+	level_assets: Option<LevelAssets>,
+	failed: bool,
+}
+
+impl LoadingState {
+	pub fn new(manifest: LevelManifest) -> Self {
+		LoadingState {
+			manifest: Some(manifest),
+			progress: ProgressCounter::new(),
+			level_progress: None,
+			sprite_sheet_handle: None,
+			autotile_handle: None,
+			level_assets: None,
+			failed: false,
+		}
+	}
+}
+
+impl SimpleState for LoadingState {
+	fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+		let world = data.world;
+
+		let manifest = self.manifest.as_ref().unwrap();
+		match manifest.get(0) {
+			Some(entry) => {
+				self.sprite_sheet_handle = Some(load_sprite_sheet(world, &mut self.progress));
+				self.autotile_handle = Some(load_autotile_rules(world, &mut self.progress));
+				let mut level_progress = ProgressCounter::new();
+				self.level_assets = Some(LevelAssets::load(world, entry, &mut level_progress));
+				self.level_progress = Some(level_progress);
+			},
+			None => {
+				error!("The level manifest has no levels!");
+				self.failed = true;
+			},
+		}
+	}
+
+	fn update(&mut self, _: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+		if self.failed {
+			return Trans::Quit;
+		}
+		if !self.progress.is_complete() || !self.level_progress.as_ref().map_or(true, ProgressCounter::is_complete) {
+			return Trans::None;
+		}
+		if self.progress.num_failed() > 0 {
+			for failure in self.progress.errors() {
+				error!("Failed to load '{}': {}", failure.asset_name, failure.error);
+			}
+			return Trans::Quit;
+		}
+
+		info!("Loaded {} assets", self.progress.num_finished());
+		Trans::Switch(Box::new(LevelState::new(
+			self.manifest.take().unwrap(),
+			self.sprite_sheet_handle.take().unwrap(),
+			self.autotile_handle.take().unwrap(),
+			self.level_assets.take().unwrap(),
+			self.level_progress.take().unwrap(),
+		)))
+	}
+}
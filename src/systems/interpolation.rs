@@ -0,0 +1,115 @@
+/*******************************************************************************
+ *
+ * Copyright (c) 2020 Gnarwhal
+ *
+ * -----------------------------------------------------------------------------
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files(the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ *
+ *******************************************************************************/
+
+use std::time::Instant;
+
+use amethyst::{
+	core::{
+		timing::Time,
+		Transform,
+	},
+	derive::SystemDesc,
+	ecs::{Join, Read, ReadStorage, System, SystemData, WriteStorage},
+};
+use crate::components::Interpolation;
+
+// Anything that moves an interpolated entity outside of the physics, like a
+// level reload, teleports it.
+fn check_teleport(transform: &Transform, interpolation: &mut Interpolation) {
+	if *transform.translation() != interpolation.rendered {
+		interpolation.teleport(*transform.translation());
+	}
+}
+
+// How far the game is between the last physics step and the next one.
+fn interpolation_alpha(time: &Time) -> f32 {
+	let since_step = Instant::now()
+		.checked_duration_since(time.last_fixed_update())
+		.unwrap_or_default();
+	(since_step.as_secs_f32() / time.fixed_seconds()).min(1.0)
+}
+
+/// Runs at the start of every physics step, putting interpolated entities
+/// back where the physics left them.
+#[derive(SystemDesc)]
+pub struct InterpolationStartSystem;
+
+impl<'s> System<'s> for InterpolationStartSystem {
+	type SystemData = (
+		WriteStorage<'s, Transform>,
+		WriteStorage<'s, Interpolation>,
+	);
+
+	fn run(&mut self, (mut transforms, mut interpolations): Self::SystemData) {
+		for (transform, interpolation) in (&mut transforms, &mut interpolations).join() {
+			check_teleport(transform, interpolation);
+			transform.set_translation(interpolation.current);
+			interpolation.previous = interpolation.current;
+		}
+	}
+}
+
+/// Runs at the end of every physics step, recording where interpolated
+/// entities ended up.
+#[derive(SystemDesc)]
+pub struct InterpolationEndSystem;
+
+impl<'s> System<'s> for InterpolationEndSystem {
+	type SystemData = (
+		ReadStorage<'s, Transform>,
+		WriteStorage<'s, Interpolation>,
+	);
+
+	fn run(&mut self, (transforms, mut interpolations): Self::SystemData) {
+		for (transform, interpolation) in (&transforms, &mut interpolations).join() {
+			interpolation.current = *transform.translation();
+			interpolation.rendered = interpolation.current;
+		}
+	}
+}
+
+/// Runs every frame, moving interpolated entities to where they should be
+/// drawn.
+#[derive(SystemDesc)]
+pub struct InterpolationSystem;
+
+impl<'s> System<'s> for InterpolationSystem {
+	type SystemData = (
+		WriteStorage<'s, Transform>,
+		WriteStorage<'s, Interpolation>,
+		Read<'s, Time>,
+	);
+
+	fn run(&mut self, (mut transforms, mut interpolations, time): Self::SystemData) {
+		let alpha = interpolation_alpha(&time);
+		for (transform, interpolation) in (&mut transforms, &mut interpolations).join() {
+			check_teleport(transform, interpolation);
+			let position = interpolation.previous + (interpolation.current - interpolation.previous) * alpha;
+			transform.set_translation(position);
+			interpolation.rendered = position;
+		}
+	}
+}
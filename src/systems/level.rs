@@ -0,0 +1,255 @@
+/*******************************************************************************
+ *
+ * Copyright (c) 2020 Gnarwhal
+ *
+ * -----------------------------------------------------------------------------
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files(the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ *
+ *******************************************************************************/
+
+use amethyst::{
+	core::{
+		timing::Time,
+		Transform,
+		math::{Point3, Vector2, Vector3},
+	},
+	derive::SystemDesc,
+	ecs::{Entities, Entity, Join, Read, ReadStorage, System, SystemData, Write, WriteStorage},
+	shrev::{EventChannel, ReaderId},
+	tiles::{FlatEncoder, TileMap},
+};
+use crate::components::Collider;
+use crate::components::Dynamic;
+use crate::components::Interpolation;
+use crate::components::Player;
+use crate::components::Tile;
+use crate::components::TileSprite;
+use crate::states::level::{Checkpoint, Level, LevelStats, Respawn};
+use crate::states::level::BLOCK_SIZE;
+use crate::systems::player::JUMP_COUNT;
+
+const CONTACT_EPSILON: f32 = 0.01;
+
+// Hazards only count once the player is properly inside them, so grazing a
+// corner doesn't kill
+const HAZARD_MARGIN: f32 = 2.0;
+
+// Finds a tile within `margin` of the edges of an object's collider, giving
+// its column and row from the bottom left.
+fn find_tile(object: &Vector3<f32>, collider: &Collider, level: &Level, margin: f32, predicate: impl Fn(Tile) -> bool) -> Option<(usize, usize)> {
+	if level.width == 0 || level.height == 0 {
+		return None;
+	}
+	let center = collider.center(object);
+	let reach = collider.half_extents + Vector2::new(margin, margin);
+	let left   = (((center.x - level.left  ) - reach.x) / BLOCK_SIZE).floor().max(0.0) as usize;
+	let bottom = (((center.y - level.bottom) - reach.y) / BLOCK_SIZE).floor().max(0.0) as usize;
+	let right  = ((((center.x - level.left  ) + reach.x) / BLOCK_SIZE).floor().max(0.0) as usize).min(level.width  - 1);
+	let top    = ((((center.y - level.bottom) + reach.y) / BLOCK_SIZE).floor().max(0.0) as usize).min(level.height - 1);
+	for i in left..=right {
+		for j in bottom..=top {
+			if predicate(level.tile_at(i, j)) {
+				return Some((i, j));
+			}
+		}
+	}
+	None
+}
+
+pub fn touches_tile(object: &Vector3<f32>, collider: &Collider, kind: Tile, level: &Level) -> bool {
+	find_tile(object, collider, level, CONTACT_EPSILON, |tile| tile == kind).is_some()
+}
+
+// Ends are either end tiles or end areas, which bodies can move into.
+pub fn touches_end(object: &Vector3<f32>, collider: &Collider, level: &Level) -> bool {
+	let center = collider.center(object);
+	let reach = collider.half_extents + Vector2::new(CONTACT_EPSILON, CONTACT_EPSILON);
+	touches_tile(object, collider, Tile::End, level) || level.ends.iter().any(|(bottom_left, top_right)| {
+		center.x + reach.x >= bottom_left.x && center.x - reach.x <= top_right.x
+		&& center.y + reach.y >= bottom_left.y && center.y - reach.y <= top_right.y
+	})
+}
+
+pub fn touched_hazard(object: &Vector3<f32>, collider: &Collider, level: &Level) -> Option<Tile> {
+	find_tile(object, collider, level, -HAZARD_MARGIN, Tile::is_hazard).map(|(x, y)| level.tile_at(x, y))
+}
+
+/// Sent once when the player reaches the end of a level.
+#[derive(Clone, Copy, Debug)]
+pub struct LevelCompleteEvent {
+	pub stats: LevelStats,
+}
+
+#[derive(SystemDesc)]
+pub struct LevelExitSystem;
+
+impl<'s> System<'s> for LevelExitSystem {
+	type SystemData = (
+		ReadStorage<'s, Player>,
+		ReadStorage<'s, Transform>,
+		ReadStorage<'s, Collider>,
+		Write<'s, Level>,
+		Read<'s, LevelStats>,
+		Write<'s, EventChannel<LevelCompleteEvent>>,
+	);
+
+	fn run(&mut self, (players, transforms, colliders, mut level, stats, mut completions): Self::SystemData) {
+		let default_collider = Collider::default();
+		for (_, transform, collider) in (&players, &transforms, colliders.maybe()).join() {
+			if !level.complete && touches_end(transform.translation(), collider.unwrap_or(&default_collider), &level) {
+				level.complete = true;
+				completions.single_write(LevelCompleteEvent { stats: *stats });
+			}
+		}
+	}
+}
+
+#[derive(SystemDesc)]
+#[system_desc(name(LevelStatsSystemDesc))]
+pub struct LevelStatsSystem {
+	#[system_desc(event_channel_reader)]
+	death_reader: ReaderId<DeathEvent>,
+}
+
+impl LevelStatsSystem {
+	pub fn new(death_reader: ReaderId<DeathEvent>) -> Self {
+		LevelStatsSystem {
+			death_reader,
+		}
+	}
+}
+
+// The stats stop counting once the level is complete.
+impl<'s> System<'s> for LevelStatsSystem {
+	type SystemData = (
+		ReadStorage<'s, Player>,
+		Read<'s, EventChannel<DeathEvent>>,
+		Read<'s, Level>,
+		Read<'s, Time>,
+		Write<'s, LevelStats>,
+	);
+
+	fn run(&mut self, (players, deaths, level, time, mut stats): Self::SystemData) {
+		let death_count = deaths.read(&mut self.death_reader).count();
+		if level.complete {
+			return;
+		}
+		stats.time += time.fixed_seconds();
+		stats.deaths += death_count;
+		stats.jumps = players.join().map(|player| player.jumps_taken).sum();
+	}
+}
+
+/// Sent whenever the player dies.
+#[derive(Clone, Debug)]
+pub struct DeathEvent {
+	pub entity: Entity,
+	/// Where the player was when they died.
+	pub position: Vector3<f32>,
+	pub cause: Tile,
+}
+
+#[derive(SystemDesc)]
+pub struct HazardSystem;
+
+impl<'s> System<'s> for HazardSystem {
+	type SystemData = (
+		Entities<'s>,
+		WriteStorage<'s, Player>,
+		WriteStorage<'s, Dynamic>,
+		WriteStorage<'s, Transform>,
+		WriteStorage<'s, Interpolation>,
+		ReadStorage<'s, Collider>,
+		Read<'s, Level>,
+		Read<'s, Respawn>,
+		Write<'s, EventChannel<DeathEvent>>,
+	);
+
+	fn run(&mut self, (entities, mut players, mut dynamics, mut transforms, mut interpolations, colliders, level, respawn, mut deaths): Self::SystemData) {
+		let default_collider = Collider::default();
+		for (entity, player, dynamic, transform, collider) in (&entities, &mut players, &mut dynamics, &mut transforms, colliders.maybe()).join() {
+			if let Some(cause) = touched_hazard(transform.translation(), collider.unwrap_or(&default_collider), &level) {
+				deaths.single_write(DeathEvent {
+					entity,
+					position: *transform.translation(),
+					cause,
+				});
+				transform.set_translation_xyz(respawn.position.x, respawn.position.y, 0.0);
+				if let Some(interpolation) = interpolations.get_mut(entity) {
+					interpolation.teleport(*transform.translation());
+				}
+				*dynamic = Dynamic::default();
+				player.reset_jumps(JUMP_COUNT);
+			}
+		}
+	}
+}
+
+#[derive(SystemDesc)]
+pub struct CheckpointSystem;
+
+impl<'s> System<'s> for CheckpointSystem {
+	type SystemData = (
+		ReadStorage<'s, Player>,
+		ReadStorage<'s, Transform>,
+		ReadStorage<'s, Collider>,
+		WriteStorage<'s, TileMap<TileSprite, FlatEncoder>>,
+		Read<'s, Level>,
+		Write<'s, Respawn>,
+	);
+
+	fn run(&mut self, (players, transforms, colliders, mut tile_maps, level, mut respawn): Self::SystemData) {
+		let default_collider = Collider::default();
+		for (_, transform, collider) in (&players, &transforms, colliders.maybe()).join() {
+			let (x, y) = match find_tile(transform.translation(), collider.unwrap_or(&default_collider), &level, CONTACT_EPSILON, |tile| tile == Tile::Checkpoint) {
+				Some(position) => position,
+				None => continue,
+			};
+			if respawn.checkpoint.map_or(false, |checkpoint| checkpoint.x == x && checkpoint.y == y) {
+				continue;
+			}
+
+			// Swap the sprites of the old and new checkpoints on the gameplay
+			// tile map, which counts rows from the top
+			let mut sprite = None;
+			if let Some(tile_map) = level.entities.first().and_then(|entity| tile_maps.get_mut(*entity)) {
+				if let Some(previous) = respawn.checkpoint {
+					if let Some(tile) = tile_map.get_mut(&Point3::new(previous.x as u32, (level.height - previous.y - 1) as u32, 0)) {
+						tile.0 = previous.sprite;
+					}
+				}
+				if let Some(tile) = tile_map.get_mut(&Point3::new(x as u32, (level.height - y - 1) as u32, 0)) {
+					sprite = tile.0;
+					if let Some(active) = level.active_sprite(x, y, sprite) {
+						tile.0 = Some(active);
+					}
+				}
+			}
+
+			*respawn = Respawn {
+				position: Vector2::new(
+					level.left   + (x as f32 + 0.5) * BLOCK_SIZE,
+					level.bottom + (y as f32 + 0.5) * BLOCK_SIZE,
+				),
+				checkpoint: Some(Checkpoint { x, y, sprite }),
+			};
+		}
+	}
+}
@@ -0,0 +1,60 @@
+/*******************************************************************************
+ *
+ * Copyright (c) 2020 Gnarwhal
+ *
+ * -----------------------------------------------------------------------------
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files(the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ *
+ *******************************************************************************/
+
+use amethyst::{
+	core::{
+		math::Vector3,
+		Transform,
+	},
+	derive::SystemDesc,
+	ecs::{Join, ReadStorage, System, SystemData, WriteStorage},
+	renderer::Camera,
+};
+use crate::components::Parallax;
+
+#[derive(SystemDesc)]
+pub struct ParallaxSystem;
+
+// The level is centered on the origin, so that's where parallax layers line
+// up with it.
+impl<'s> System<'s> for ParallaxSystem {
+	type SystemData = (
+		ReadStorage<'s, Parallax>,
+		ReadStorage<'s, Camera>,
+		WriteStorage<'s, Transform>,
+	);
+
+	fn run(&mut self, (parallaxes, cameras, mut transforms): Self::SystemData) {
+		let mut camera_translation = Vector3::new(0.0, 0.0, 0.0);
+		for (camera_transform, _) in (&transforms, &cameras).join() {
+			camera_translation = *camera_transform.translation();
+		}
+		for (transform, parallax) in (&mut transforms, &parallaxes).join() {
+			transform.set_translation_x(camera_translation.x * (1.0 - parallax.scroll.x));
+			transform.set_translation_y(camera_translation.y * (1.0 - parallax.scroll.y));
+		}
+	}
+}
@@ -1,164 +1,534 @@
-/*******************************************************************************
- *
- * Copyright (c) 2020 Gnarwhal
- *
- * -----------------------------------------------------------------------------
- *
- * Permission is hereby granted, free of charge, to any person obtaining a copy
- * of this software and associated documentation files(the "Software"), to deal
- * in the Software without restriction, including without limitation the rights
- * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
- * copies of the Software, and to permit persons to whom the Software is
- * furnished to do so, subject to the following conditions
- *
- * The above copyright notice and this permission notice shall be included in all
- * copies or substantial portions of the Software.
- *
- * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
- * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
- * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
- * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
- * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
- * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
- * SOFTWARE.
- *
- *******************************************************************************/
-
-use amethyst::{
-	core::{
-		timing::Time,
-		math::Vector3,
-	},
-	core::{Transform},
-	derive::SystemDesc,
-	ecs::{Join, Read, ReadStorage, System, SystemData, WriteStorage},
-};
-use crate::components::Dynamic;
-use crate::components::Gravity;
-use crate::components::Tile;
-use crate::systems::player::FULL_HOP_TIME;
-use crate::systems::player::FULL_HOP_HEIGHT;
-use crate::systems::player::MAX_GROUND_SPEED;
-use crate::systems::player::MAX_AERIAL_SPEED;
-use crate::states::level::Level;
-use crate::states::level::BLOCK_SIZE;
-
-pub const FRICTION:       f32 = MAX_GROUND_SPEED / 0.1;
-pub const AIR_RESISTANCE: f32 = MAX_AERIAL_SPEED / 0.5;
-
-pub const GRAVITY: f32 = -2.0 * FULL_HOP_HEIGHT / (FULL_HOP_TIME * FULL_HOP_TIME);
-
-#[derive(SystemDesc)]
-pub struct ForceSystem;
-
-fn apply_resistance(velocity: f32, resistance: f32) -> f32 {
-	if velocity < 0.0 {
-		(velocity + resistance).min(0.0)
-	} else if velocity > 0.0 {
-		(velocity - resistance).max(0.0)
-	} else {
-		0.0
-	}
-}
-
-impl<'s> System<'s> for ForceSystem {
-	type SystemData = (
-		WriteStorage<'s, Dynamic>,
-		ReadStorage<'s, Gravity>,
-		Read<'s, Time>,
-	);
-
-	fn run(&mut self, (mut dynamics, gravities, delta_time): Self::SystemData) {
-		for dynamic in (&mut dynamics).join() {
-			let velocity = &mut dynamic.velocity;
-			velocity.x = apply_resistance(velocity.x, match dynamic.grounded { true => FRICTION, false => AIR_RESISTANCE, } * dynamic.friction_coefficient * delta_time.delta_seconds());
-		}
-		for (dynamic, _) in (&mut dynamics, &gravities).join() {
-			dynamic.velocity.y += GRAVITY * delta_time.delta_seconds();
-		}
-	}
-}
-
-#[derive(SystemDesc)]
-pub struct CollisionSystem;
-
-fn attempt_collision(object: &mut Vector3<f32>, level: &Read<Level>, tiles: &ReadStorage<Tile>) -> u32 {
-	let left   = (((object.x - level.left  ) / BLOCK_SIZE - 0.5).floor() as usize).min(level.width  - 1).max(0);
-	let bottom = (((object.y - level.bottom) / BLOCK_SIZE - 0.5).floor() as usize).min(level.height - 1).max(0);
-	let right  = (((object.x - level.left  ) / BLOCK_SIZE - 0.5).ceil()  as usize).min(level.width  - 1).max(0);
-	let top    = (((object.y - level.bottom) / BLOCK_SIZE - 0.5).ceil()  as usize).min(level.height - 1).max(0);
-	let mut closest = Option::<(usize, usize)>::None;
-	let mut distance = (BLOCK_SIZE, BLOCK_SIZE, BLOCK_SIZE * BLOCK_SIZE);
-	for i in left..=right {
-		for j in bottom..=top {
-			match (&mut closest, tiles.get(level.entities[(level.height - j - 1) * level.width + i]).unwrap()) {
-				(None, Tile::Ground) | (None, Tile::Start) | (None, Tile::End) => {
-					closest = Some((i, j));
-					let dist_x = (object.x - level.left  ) - (i as f32 + 0.5) * BLOCK_SIZE;
-					let dist_y = (object.y - level.bottom) - (j as f32 + 0.5) * BLOCK_SIZE;
-					distance = (dist_x, dist_y, (dist_x * dist_x + dist_y * dist_y).sqrt());
-				},
-				(Some(_), Tile::Ground) | (Some(_), Tile::Start) | (Some(_), Tile::End) => {
-					let dist_x = (object.x - level.left  ) - (i as f32 + 0.5) * BLOCK_SIZE;
-					let dist_y = (object.y - level.bottom) - (j as f32 + 0.5) * BLOCK_SIZE;
-					let current_distance = (dist_x * dist_x + dist_y * dist_y).sqrt();
-					if current_distance < distance.2 {
-						closest = Some((i, j));
-						distance = (dist_x, dist_y, current_distance);
-					}
-				}
-				(_, Tile::Background) => {},
-			}
-		}
-	}
-	if let Some((x, y)) = closest {
-		if distance.0.abs() > distance.1.abs() {
-			object.x += (BLOCK_SIZE - distance.0.abs()) * distance.0.signum();
-			(2 - distance.0.signum() as i32) as u32
-		} else {
-			object.y += (BLOCK_SIZE - distance.1.abs()) * distance.1.signum();
-			(3 - distance.1.signum() as i32) as u32
-		}
-	} else {
-		0
-	}
-}
-
-impl<'s> System<'s> for CollisionSystem {
-	type SystemData = (
-		WriteStorage<'s, Transform>,
-		WriteStorage<'s, Dynamic>,
-		ReadStorage<'s, Tile>,
-		Read<'s, Level>,
-		Read<'s, Time>,
-	);
-
-	fn run(&mut self, (mut transforms, mut dynamics, tiles, level, delta_time): Self::SystemData) {
-		for (transform, dynamic) in (&mut transforms, &mut dynamics).join() {
-			let translation = transform.translation_mut();
-			translation.x += dynamic.velocity.x * delta_time.delta_seconds();
-			translation.y += dynamic.velocity.y * delta_time.delta_seconds();
-			dynamic.grounded = false;
-			let mut result = 1;
-			while result != 0 {
-				result = attempt_collision(translation, &level, &tiles);
-				match result {
-					1 => {
-						dynamic.velocity.x = 0.0;
-					},
-					2 => {
-						dynamic.velocity.y = 0.0;
-						dynamic.grounded = true;
-					},
-					3 => {
-						dynamic.velocity.x = 0.0;
-					},
-					4 => {
-						dynamic.velocity.y = 0.0;
-					},
-					_ => {}
-				}
-			}
-		}
-	}
-}
+/*******************************************************************************
+ *
+ * Copyright (c) 2020 Gnarwhal
+ *
+ * -----------------------------------------------------------------------------
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files(the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ *
+ *******************************************************************************/
+
+use std::cmp::Ordering;
+
+use amethyst::{
+	core::{
+		timing::Time,
+		math::Vector3,
+	},
+	core::{Transform},
+	derive::SystemDesc,
+	ecs::{Join, Read, ReadStorage, System, SystemData, WriteStorage},
+};
+use crate::components::Collider;
+use crate::components::Dynamic;
+use crate::components::Gravity;
+use crate::components::Surface;
+use crate::components::Tile;
+use crate::systems::player::FULL_HOP_TIME;
+use crate::systems::player::FULL_HOP_HEIGHT;
+use crate::systems::player::MAX_GROUND_SPEED;
+use crate::systems::player::MAX_AERIAL_SPEED;
+use crate::states::level::Level;
+use crate::states::level::BLOCK_SIZE;
+
+pub const FRICTION:       f32 = MAX_GROUND_SPEED / 0.1;
+pub const AIR_RESISTANCE: f32 = MAX_AERIAL_SPEED / 0.5;
+
+pub const GRAVITY: f32 = -2.0 * FULL_HOP_HEIGHT / (FULL_HOP_TIME * FULL_HOP_TIME);
+
+/// Length of a physics step in seconds.
+pub const PHYSICS_STEP: f32 = 1.0 / 60.0;
+
+const MIN_BOUNCE_SPEED: f32 = 4.0 * BLOCK_SIZE;
+
+/// Fastest a body can move along either axis, in units per second.
+pub const MAX_SPEED: f32 = 64.0 * BLOCK_SIZE;
+
+// Bodies move at most this far between collision checks. Anything under half
+// a block can't skip past a tile or get pushed out the wrong side of one.
+const MAX_STEP_DISTANCE: f32 = BLOCK_SIZE / 4.0;
+
+// How far a slope's floor can be below a grounded body and still pull it
+// down, or above any body and still push it up. This leaves room over the
+// furthest a single step can move a body along a slope.
+const SLOPE_SNAP: f32 = BLOCK_SIZE / 2.0;
+
+const SLOPE_TOLERANCE: f32 = 0.01;
+
+// The most tiles a body gets pushed out of in one step. A body that doesn't
+// fit where it is, like one wider than the shaft it's in, would otherwise get
+// pushed back and forth forever.
+const MAX_PUSHES: usize = 8;
+
+const PLATFORM_TOLERANCE: f32 = 0.5;
+
+#[derive(SystemDesc)]
+pub struct ForceSystem;
+
+fn apply_resistance(velocity: f32, resistance: f32) -> f32 {
+	if velocity < 0.0 {
+		(velocity + resistance).min(0.0)
+	} else if velocity > 0.0 {
+		(velocity - resistance).max(0.0)
+	} else {
+		0.0
+	}
+}
+
+pub fn apply_forces(dynamic: &mut Dynamic, gravity: bool, delta_seconds: f32) {
+	let velocity = &mut dynamic.velocity;
+	velocity.x = apply_resistance(velocity.x, match dynamic.grounded { true => FRICTION * dynamic.surface.friction, false => AIR_RESISTANCE, } * dynamic.friction_coefficient * delta_seconds);
+	if gravity {
+		velocity.y += GRAVITY * delta_seconds;
+	}
+}
+
+impl<'s> System<'s> for ForceSystem {
+	type SystemData = (
+		WriteStorage<'s, Dynamic>,
+		ReadStorage<'s, Gravity>,
+		Read<'s, Time>,
+	);
+
+	fn run(&mut self, (mut dynamics, gravities, delta_time): Self::SystemData) {
+		for (dynamic, gravity) in (&mut dynamics, gravities.maybe()).join() {
+			apply_forces(dynamic, gravity.is_some(), delta_time.fixed_seconds());
+		}
+	}
+}
+
+#[derive(SystemDesc)]
+pub struct CollisionSystem;
+
+// Slopes are solid from the side, up to the height of their edge facing the
+// body, so their tall side is a wall. The slope a body stands on is handled
+// by `slope_floor` instead. One-way platforms only collide with a body whose
+// bottom was at or above their top before it moved, which `previous_bottom`
+// gives. It is None while platforms should be passed through.
+fn tile_height(level: &Level, x: usize, y: usize, column: isize, previous_bottom: Option<f32>) -> f32 {
+	let tile = level.tile_at(x, y);
+	match (tile, tile.slope()) {
+		(Tile::Platform, _) => match previous_bottom.map_or(false, |bottom| bottom >= level.bottom + (y + 1) as f32 * BLOCK_SIZE - PLATFORM_TOLERANCE) {
+			true => BLOCK_SIZE,
+			false => 0.0,
+		},
+		(_, Some((left, right))) => match (x as isize).cmp(&column) {
+			Ordering::Less    => right * BLOCK_SIZE,
+			Ordering::Equal   => 0.0,
+			Ordering::Greater => left * BLOCK_SIZE,
+		},
+		(tile, None) => match tile.is_solid() {
+			true => BLOCK_SIZE,
+			false => 0.0,
+		},
+	}
+}
+
+// Tiles beside the slope a body is standing on that are no taller than the
+// slope's edge on that side get walked onto rather than into.
+fn steps_off_slope(level: &Level, x: usize, top: f32, slope: Option<(f32, usize, usize)>) -> bool {
+	match slope {
+		Some((_, column, row)) if x != column => match level.tile_at(column, row).slope() {
+			Some((left, right)) => {
+				let edge = if x > column { right } else { left };
+				top <= level.bottom + (row as f32 + edge) * BLOCK_SIZE + SLOPE_TOLERANCE
+			},
+			None => false,
+		},
+		_ => false,
+	}
+}
+
+fn attempt_collision(object: &mut Vector3<f32>, collider: &Collider, level: &Level, previous_bottom: Option<f32>) -> (u32, Surface) {
+	let center = collider.center(object);
+	let half_extents = collider.half_extents;
+	let left   = (((center.x - half_extents.x - level.left  ) / BLOCK_SIZE).floor() as usize).min(level.width  - 1).max(0);
+	let bottom = (((center.y - half_extents.y - level.bottom) / BLOCK_SIZE).floor() as usize).min(level.height - 1).max(0);
+	let right  = ((((center.x + half_extents.x - level.left  ) / BLOCK_SIZE).ceil() - 1.0) as usize).min(level.width  - 1).max(0);
+	let top    = ((((center.y + half_extents.y - level.bottom) / BLOCK_SIZE).ceil() - 1.0) as usize).min(level.height - 1).max(0);
+	let column = ((center.x - level.left) / BLOCK_SIZE).floor() as isize;
+	let slope = slope_floor(center.x, collider.bottom(object), SLOPE_SNAP, level);
+	let mut closest = Option::<(usize, usize)>::None;
+	// Distance from the closest tile's center, and that tile's height
+	let mut distance = (BLOCK_SIZE, BLOCK_SIZE, BLOCK_SIZE * BLOCK_SIZE);
+	let mut height = BLOCK_SIZE;
+	for i in left..=right {
+		for j in bottom..=top {
+			let cell_height = tile_height(level, i, j, column, previous_bottom);
+			let tile_bottom = level.bottom + j as f32 * BLOCK_SIZE;
+			if cell_height <= 0.0 || steps_off_slope(level, i, tile_bottom + cell_height, slope) {
+				continue;
+			}
+			let dist_x = (center.x - level.left) - (i as f32 + 0.5) * BLOCK_SIZE;
+			let dist_y = center.y - (tile_bottom + cell_height / 2.0);
+			let current_distance = (dist_x * dist_x + dist_y * dist_y).sqrt();
+			if closest.is_none() || current_distance < distance.2 {
+				closest = Some((i, j));
+				distance = (dist_x, dist_y, current_distance);
+				height = cell_height;
+			}
+		}
+	}
+	if let Some((x, y)) = closest {
+		// Push out along whichever axis the body overlaps the tile least on
+		let reach_x = half_extents.x + BLOCK_SIZE / 2.0;
+		let reach_y = half_extents.y + height / 2.0;
+		if level.tile_at(x, y) == Tile::Platform {
+			object.y += reach_y - distance.1;
+			(2, level.surface_at(x, y))
+		} else if reach_x - distance.0.abs() < reach_y - distance.1.abs() {
+			object.x += (reach_x - distance.0.abs()) * distance.0.signum();
+			((2 - distance.0.signum() as i32) as u32, level.surface_at(x, y))
+		} else {
+			object.y += (reach_y - distance.1.abs()) * distance.1.signum();
+			((3 - distance.1.signum() as i32) as u32, level.surface_at(x, y))
+		}
+	} else {
+		(0, Surface::default())
+	}
+}
+
+// Finds the floor of a slope under a body's bottom center, if it's no more
+// than `reach_down` below the body or `SLOPE_SNAP` above it. Walking along a
+// slope can cross into the row above or below, so those get checked too.
+fn slope_floor(center_x: f32, bottom: f32, reach_down: f32, level: &Level) -> Option<(f32, usize, usize)> {
+	let x = (center_x - level.left) / BLOCK_SIZE;
+	if x < 0.0 || x >= level.width as f32 {
+		return None;
+	}
+	let column = x.floor() as usize;
+	let fraction = x - x.floor();
+	let row = ((bottom - level.bottom) / BLOCK_SIZE).floor() as isize;
+	for j in (row - 1..=row + 1).rev() {
+		if j < 0 || j >= level.height as isize {
+			continue;
+		}
+		if let Some((left, right)) = level.tile_at(column, j as usize).slope() {
+			let floor = level.bottom + (j as f32 + left + (right - left) * fraction) * BLOCK_SIZE;
+			if floor >= bottom - reach_down && floor <= bottom + SLOPE_SNAP {
+				return Some((floor, column, j as usize));
+			}
+		}
+	}
+	None
+}
+
+// Finds the top of the solid tile under a body's bottom center, if it's no
+// more than `SLOPE_SNAP` below the body. This is where a body walking off the
+// low end of a slope ends up.
+fn flat_floor(center_x: f32, bottom: f32, level: &Level) -> Option<(f32, usize, usize)> {
+	let x = (center_x - level.left) / BLOCK_SIZE;
+	if x < 0.0 || x >= level.width as f32 {
+		return None;
+	}
+	let column = x.floor() as usize;
+	let row = ((bottom - level.bottom) / BLOCK_SIZE).floor() as isize - 1;
+	if row < 0 || row >= level.height as isize || !level.tile_at(column, row as usize).is_solid() {
+		return None;
+	}
+	let floor = level.bottom + (row + 1) as f32 * BLOCK_SIZE;
+	match floor >= bottom - SLOPE_SNAP {
+		true => Some((floor, column, row as usize)),
+		false => None,
+	}
+}
+
+// Moving in small enough steps to check for collisions after each one means
+// fast bodies can't tunnel through thin ground.
+pub fn move_and_collide(translation: &mut Vector3<f32>, dynamic: &mut Dynamic, collider: &Collider, level: &Level, delta_seconds: f32) {
+	dynamic.velocity.x = dynamic.velocity.x.max(-MAX_SPEED).min(MAX_SPEED);
+	dynamic.velocity.y = dynamic.velocity.y.max(-MAX_SPEED).min(MAX_SPEED);
+	let distance = dynamic.velocity.x.abs().max(dynamic.velocity.y.abs()) * delta_seconds;
+	let steps = ((distance / MAX_STEP_DISTANCE).ceil() as usize).max(1);
+	let step_seconds = delta_seconds / steps as f32;
+
+	let was_grounded = dynamic.grounded;
+	dynamic.grounded = false;
+	let mut on_slope = was_grounded && slope_floor(collider.center(translation).x, collider.bottom(translation), SLOPE_TOLERANCE, level).is_some();
+	let mut bounced = false;
+	for _ in 0..steps {
+		let previous_bottom = match dynamic.drop_through || dynamic.velocity.y > 0.0 {
+			true => None,
+			false => Some(collider.bottom(translation)),
+		};
+		translation.x += dynamic.velocity.x * step_seconds;
+		translation.y += dynamic.velocity.y * step_seconds;
+		let mut result = 1;
+		let mut pushes = 0;
+		while result != 0 && pushes < MAX_PUSHES {
+			pushes += 1;
+			let (side, surface) = attempt_collision(translation, collider, level, previous_bottom);
+			result = side;
+			match result {
+				1 => {
+					dynamic.velocity.x = 0.0;
+				},
+				2 => {
+					// Bouncing off of a surface too softly counts as landing on it
+					let bounce = -dynamic.velocity.y * surface.bounce;
+					if bounce > MIN_BOUNCE_SPEED {
+						dynamic.velocity.y = bounce;
+						bounced = true;
+					} else if !bounced {
+						dynamic.velocity.y = 0.0;
+						dynamic.grounded = true;
+					}
+					dynamic.surface = surface;
+				},
+				3 => {
+					dynamic.velocity.x = 0.0;
+				},
+				4 => {
+					dynamic.velocity.y = 0.0;
+				},
+				_ => {}
+			}
+		}
+
+		// Slopes hold up the bottom center of a body. Grounded bodies get
+		// pulled down onto them as well, and onto the ground past the low end
+		// of one, so walking down a slope doesn't leave the ground
+		if dynamic.velocity.y <= 0.0 {
+			let held = was_grounded || dynamic.grounded;
+			let reach_down = match held {
+				true => SLOPE_SNAP,
+				false => 0.0,
+			};
+			let center_x = collider.center(translation).x;
+			let bottom = collider.bottom(translation);
+			let slope = slope_floor(center_x, bottom, reach_down, level);
+			let floor = match (slope, held && on_slope) {
+				(Some(slope), _) => Some(slope),
+				(None, true) => flat_floor(center_x, bottom, level),
+				(None, false) => None,
+			};
+			on_slope = slope.is_some();
+			if let Some((floor, x, y)) = floor {
+				translation.y += floor - bottom;
+				dynamic.velocity.y = 0.0;
+				dynamic.grounded = true;
+				dynamic.surface = level.surface_at(x, y);
+			}
+		}
+	}
+}
+
+impl<'s> System<'s> for CollisionSystem {
+	type SystemData = (
+		WriteStorage<'s, Transform>,
+		WriteStorage<'s, Dynamic>,
+		ReadStorage<'s, Collider>,
+		Read<'s, Level>,
+		Read<'s, Time>,
+	);
+
+	fn run(&mut self, (mut transforms, mut dynamics, colliders, level, delta_time): Self::SystemData) {
+		let default_collider = Collider::default();
+		for (transform, dynamic, collider) in (&mut transforms, &mut dynamics, colliders.maybe()).join() {
+			move_and_collide(transform.translation_mut(), dynamic, collider.unwrap_or(&default_collider), &level, delta_time.fixed_seconds());
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use amethyst::core::math::{Vector2, Vector3};
+	use crate::components::{Collider, Dynamic, Tile};
+	use crate::levels::LevelData;
+	use crate::states::level::{Level, BLOCK_SIZE};
+	use crate::systems::player::MAX_GROUND_SPEED;
+	use super::{move_and_collide, GRAVITY, MAX_SPEED, PHYSICS_STEP};
+
+	// '#' is ground, '.' is background and slopes use the same characters as
+	// text levels.
+	fn level(rows: &[&str]) -> Level {
+		let width = rows[0].len();
+		let height = rows.len();
+		let tiles = rows
+			.iter()
+			.flat_map(|row| row.chars())
+			.map(|character| match character {
+				'#'  => Tile::Ground,
+				'/'  => Tile::SlopeRight,
+				'\\' => Tile::SlopeLeft,
+				','  => Tile::GentleSlopeRightLow,
+				'\'' => Tile::GentleSlopeRightHigh,
+				';'  => Tile::GentleSlopeLeftLow,
+				'`'  => Tile::GentleSlopeLeftHigh,
+				_    => Tile::Background,
+			})
+			.collect();
+		Level::new(&LevelData::new(width, height, tiles, vec![None; width * height]))
+	}
+
+	// A one tile thick floor with open space both above and below it.
+	fn thin_floor() -> Level {
+		let mut rows = vec!["..."; 40];
+		rows[30] = "###";
+		level(&rows)
+	}
+
+	// The top of the floor, with rows counted from the bottom.
+	fn floor_top(level: &Level, row: usize) -> f32 {
+		level.bottom + (row + 1) as f32 * BLOCK_SIZE
+	}
+
+	// Moves a body until it lands, giving up once it has had long enough to
+	// fall the whole height of the level.
+	fn drop(level: &Level, start: Vector3<f32>, velocity: (f32, f32), delta_seconds: f32) -> (Vector3<f32>, Dynamic) {
+		let collider = Collider::default();
+		let mut translation = start;
+		let mut dynamic = Dynamic::default();
+		dynamic.velocity.x = velocity.0;
+		dynamic.velocity.y = velocity.1;
+		let fall_time = (level.top - level.bottom) / velocity.1.abs().min(MAX_SPEED);
+		for _ in 0..=(fall_time / delta_seconds).ceil() as usize {
+			move_and_collide(&mut translation, &mut dynamic, &collider, level, delta_seconds);
+			if dynamic.grounded {
+				break;
+			}
+		}
+		(translation, dynamic)
+	}
+
+	#[test]
+	fn falling_bodies_land_on_thin_floors() {
+		let level = thin_floor();
+		let top = floor_top(&level, 9);
+		let start = Vector3::new(level.left + 1.5 * BLOCK_SIZE, level.top - BLOCK_SIZE, 0.0);
+		for &speed in &[BLOCK_SIZE, MAX_SPEED / 2.0, MAX_SPEED, MAX_SPEED * 10.0] {
+			for &delta_seconds in &[PHYSICS_STEP, 0.1, 0.5, 1.0, 5.0] {
+				let (translation, dynamic) = drop(&level, start, (0.0, -speed), delta_seconds);
+				let bottom = Collider::default().bottom(&translation);
+				assert!(dynamic.grounded, "not grounded at speed {} with step {}", speed, delta_seconds);
+				assert!((bottom - top).abs() < 0.001, "resting at {} instead of {} at speed {} with step {}", bottom, top, speed, delta_seconds);
+				assert_eq!(dynamic.velocity.y, 0.0);
+			}
+		}
+	}
+
+	#[test]
+	fn diagonal_bodies_land_on_thin_floors() {
+		let mut rows = vec!["........................................"; 40];
+		rows[30] = "########################################";
+		let level = level(&rows);
+		let top = floor_top(&level, 9);
+		let start = Vector3::new(level.left + 1.5 * BLOCK_SIZE, level.top - BLOCK_SIZE, 0.0);
+		for &delta_seconds in &[PHYSICS_STEP, 0.1, 0.5] {
+			let (translation, dynamic) = drop(&level, start, (MAX_SPEED / 4.0, -MAX_SPEED), delta_seconds);
+			let bottom = Collider::default().bottom(&translation);
+			assert!(dynamic.grounded, "not grounded with step {}", delta_seconds);
+			assert!((bottom - top).abs() < 0.001, "resting at {} instead of {} with step {}", bottom, top, delta_seconds);
+		}
+	}
+
+	#[test]
+	fn wide_bodies_in_narrow_shafts_stop_pushing() {
+		let level = level(&["#..#"; 6]);
+		let collider = Collider::new(Vector2::new(1.5 * BLOCK_SIZE, BLOCK_SIZE / 2.0), Vector2::new(0.0, 0.0));
+		let mut translation = Vector3::new(0.0, 0.0, 0.0);
+		let mut dynamic = Dynamic::default();
+		// Only has to finish, since there's nowhere the body fits
+		move_and_collide(&mut translation, &mut dynamic, &collider, &level, PHYSICS_STEP);
+		assert!(translation.x.is_finite() && translation.y.is_finite());
+	}
+
+	// Walks a body along the ground at a constant speed for at least
+	// `distance`, checking that it never leaves the ground or gets stopped.
+	fn walk(level: &Level, start: Vector3<f32>, speed: f32, distance: f32) -> Vector3<f32> {
+		let collider = Collider::default();
+		let mut translation = start;
+		let mut dynamic = Dynamic::default();
+		dynamic.grounded = true;
+		for step in 0..(distance / (speed.abs() * PHYSICS_STEP)).ceil() as usize {
+			dynamic.velocity.x = speed;
+			dynamic.velocity.y += GRAVITY * PHYSICS_STEP;
+			move_and_collide(&mut translation, &mut dynamic, &collider, level, PHYSICS_STEP);
+			assert!(dynamic.grounded, "left the ground on step {} at {:?}", step, translation);
+			assert_eq!(dynamic.velocity.x, speed, "stopped on step {} at {:?}", step, translation);
+		}
+		translation
+	}
+
+	// A ramp between flat ground a row apart, with the row of the ramp given.
+	fn ramp(row: &str) -> Level {
+		level(&[
+			"..........",
+			"..........",
+			row,
+			"##########",
+		])
+	}
+
+	fn assert_standing_on(level: &Level, translation: Vector3<f32>, rows: f32) {
+		let bottom = Collider::default().bottom(&translation);
+		let ground = level.bottom + rows * BLOCK_SIZE;
+		assert!((bottom - ground).abs() < 0.01, "standing at {} instead of {}", bottom, ground);
+	}
+
+	// Walking off the low end of a slope lands at a different point on the
+	// flat ground beside it at each of these speeds.
+	const WALKING_SPEEDS: [f32; 5] = [4.0 * BLOCK_SIZE, MAX_GROUND_SPEED, 7.0 * BLOCK_SIZE, MAX_SPEED / 8.0, 10.0 * BLOCK_SIZE];
+
+	#[test]
+	fn walking_up_and_down_ramps() {
+		// Each ramp goes up to the right
+		for row in &["....../###", ".....,'###"] {
+			let level = ramp(row);
+			for &speed in &WALKING_SPEEDS {
+				let low  = Vector3::new(level.left + 2.5 * BLOCK_SIZE, level.bottom + 1.5 * BLOCK_SIZE, 0.0);
+				let high = walk(&level, low, speed, 6.0 * BLOCK_SIZE);
+				assert_standing_on(&level, high, 2.0);
+				assert!(high.x > level.left + 8.5 * BLOCK_SIZE - 0.01, "walked up {} to {} at {}", row, high.x, speed);
+				let back = walk(&level, high, -speed, 6.0 * BLOCK_SIZE);
+				assert_standing_on(&level, back, 1.0);
+			}
+		}
+		// Each ramp goes down to the right
+		for row in &["###\\......", "###`;....."] {
+			let level = ramp(row);
+			for &speed in &WALKING_SPEEDS {
+				let high = Vector3::new(level.left + 1.5 * BLOCK_SIZE, level.bottom + 2.5 * BLOCK_SIZE, 0.0);
+				let low  = walk(&level, high, speed, 6.0 * BLOCK_SIZE);
+				assert_standing_on(&level, low, 1.0);
+				assert!(low.x > level.left + 7.5 * BLOCK_SIZE - 0.01, "walked down {} to {} at {}", row, low.x, speed);
+				let back = walk(&level, low, -speed, 6.0 * BLOCK_SIZE);
+				assert_standing_on(&level, back, 2.0);
+			}
+		}
+	}
+
+	#[test]
+	fn tall_side_of_slopes_is_a_wall() {
+		for (row, direction) in &[("..../.....", -1.0), ("....\\.....", 1.0)] {
+			let level = ramp(row);
+			let collider = Collider::default();
+			let mut translation = Vector3::new(level.left + (5.0 - 3.0 * direction + 0.5) * BLOCK_SIZE, level.bottom + 1.5 * BLOCK_SIZE, 0.0);
+			let mut dynamic = Dynamic::default();
+			for _ in 0..120 {
+				dynamic.velocity.x = direction * MAX_GROUND_SPEED;
+				dynamic.velocity.y += GRAVITY * PHYSICS_STEP;
+				move_and_collide(&mut translation, &mut dynamic, &collider, &level, PHYSICS_STEP);
+			}
+			let wall = level.left + (if *direction < 0.0 { 5.0 } else { 4.0 }) * BLOCK_SIZE;
+			assert!((translation.x - (wall - direction * BLOCK_SIZE / 2.0)).abs() < 0.01, "got through {} to {}", row, translation.x);
+			assert_standing_on(&level, translation, 1.0);
+		}
+	}
+}